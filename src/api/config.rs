@@ -0,0 +1,120 @@
+use actix_web::HttpRequest;
+use ipnet::IpNet;
+use std::env;
+use std::net::IpAddr;
+use std::str::FromStr;
+use tracing::warn;
+
+use crate::common::highlight;
+
+/// API 伺服器設定，從環境變數讀取
+#[derive(Debug, Clone)]
+pub struct ApiConfig {
+    /// 文章與列表回應的 Cache-Control max-age（秒）
+    pub post_max_age: u64,
+    /// 記憶體回應快取的最大筆數，0 代表停用
+    pub response_cache_capacity: usize,
+    /// 記憶體回應快取的存活時間（秒）
+    pub response_cache_ttl: u64,
+    /// 對外的 API 位址，用於組成資源的完整 URL；未設定時由請求的 scheme 與 Host 推得
+    pub public_base_url: Option<String>,
+    /// 停止接受連線後等待進行中請求完成的上限（秒）
+    pub shutdown_timeout: u64,
+    /// 收到關閉訊號後、停止接受連線前，讓 /readyz 先回報失敗的時間（秒）
+    pub shutdown_drain_delay: u64,
+    /// JSON 端點的每個 IP 請求額度
+    pub json_rate_limit: Quota,
+    /// 資源檔案（/api/assets、/static）的每個 IP 請求額度
+    pub asset_rate_limit: Quota,
+    /// 可信任的 reverse proxy 位址，只有來自這些位址的請求才採用 `proxy_header`
+    pub trusted_proxies: Vec<IpNet>,
+    /// reverse proxy 帶入用戶端 IP 的標頭（例如 X-Forwarded-For、X-Real-IP）
+    pub proxy_header: String,
+    /// 不受流量限制的內部用戶端
+    pub rate_limit_allowlist: Vec<IpNet>,
+    /// 是否提供 /static（預設關閉，資源請透過 /api/assets/{uuid} 取得）
+    pub serve_static: bool,
+    /// 管理端點（/api/admin/*）的 Bearer token，未設定時停用管理端點
+    pub admin_token: Option<String>,
+    /// /api/highlight.css 預設的配色
+    pub highlight_theme: String,
+}
+
+/// 每個 IP 的 token bucket 額度：每分鐘補充 `per_minute` 個，最多累積 `burst` 個
+/// `per_minute` 為 0 時不限制
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+impl ApiConfig {
+    pub fn from_env() -> Self {
+        ApiConfig {
+            post_max_age: env_or("POST_CACHE_MAX_AGE", 60),
+            response_cache_capacity: env_or("RESPONSE_CACHE_CAPACITY", 1000),
+            response_cache_ttl: env_or("RESPONSE_CACHE_TTL_SECS", 300),
+            public_base_url: env::var("API_BASE_URL")
+                .ok()
+                .map(|url| url.trim().trim_end_matches('/').to_string())
+                .filter(|url| !url.is_empty()),
+            shutdown_timeout: env_or("SHUTDOWN_TIMEOUT_SECS", 30),
+            shutdown_drain_delay: env_or("SHUTDOWN_DRAIN_DELAY_SECS", 5),
+            json_rate_limit: Quota {
+                per_minute: env_or("RATE_LIMIT_JSON_PER_MIN", 120),
+                burst: env_or("RATE_LIMIT_JSON_BURST", 30),
+            },
+            asset_rate_limit: Quota {
+                per_minute: env_or("RATE_LIMIT_ASSET_PER_MIN", 600),
+                burst: env_or("RATE_LIMIT_ASSET_BURST", 100),
+            },
+            trusted_proxies: env_networks("TRUSTED_PROXIES"),
+            proxy_header: env_or("TRUSTED_PROXY_HEADER", "X-Forwarded-For".to_string()),
+            rate_limit_allowlist: env_networks("RATE_LIMIT_ALLOWLIST"),
+            serve_static: env_or("SERVE_STATIC", false),
+            admin_token: env::var("ADMIN_TOKEN").ok().filter(|token| !token.trim().is_empty()),
+            highlight_theme: env_or("HIGHLIGHT_THEME", highlight::DEFAULT_THEME.to_string()),
+        }
+    }
+}
+
+impl ApiConfig {
+    /// 組成資源 URL 的 API 位址：優先使用 API_BASE_URL，否則依請求的 scheme 與 Host
+    pub fn base_url(&self, req: &HttpRequest) -> String {
+        match &self.public_base_url {
+            Some(url) => url.clone(),
+            None => {
+                let info = req.connection_info();
+                format!("{}://{}", info.scheme(), info.host())
+            }
+        }
+    }
+}
+
+/// 讀取環境變數並解析，未設定或格式錯誤時使用預設值
+pub(crate) fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// 讀取以逗號分隔的 IP 或 CIDR 清單，無法解析的項目會被略過並記錄警告
+fn env_networks(key: &str) -> Vec<IpNet> {
+    let value = env::var(key).unwrap_or_default();
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .filter_map(|item| {
+            let network = item
+                .parse::<IpNet>()
+                .or_else(|_| item.parse::<IpAddr>().map(IpNet::from))
+                .ok();
+            if network.is_none() {
+                warn!(key, item, "ignoring invalid IP address or network");
+            }
+            network
+        })
+        .collect()
+}
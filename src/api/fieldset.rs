@@ -0,0 +1,159 @@
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::SystemTime;
+use tokio_postgres::types::Json;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+use crate::common::models::{PostAssetResponse, PostFieldsQuery, PostResponse};
+use crate::common::render::render_html;
+
+/// 文章回應要包含的欄位與關聯資料，由 `fields` 與 `include` 決定
+/// 查詢時只 SELECT 需要的欄位，`include=assets` 時才查詢 post_assets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostFieldset {
+    /// 依 `PostResponse::FIELDS` 的順序排列
+    pub fields: Vec<&'static str>,
+    pub assets: bool,
+    pub tags: bool,
+}
+
+impl PostFieldset {
+    /// 未指定 `fields` 時使用 `PostResponse::DEFAULT_FIELDS`；有未知的名稱時回傳錯誤訊息
+    pub fn parse(query: &PostFieldsQuery) -> Result<Self, String> {
+        let fields = match query.fields.as_deref() {
+            Some(fields) => parse_list(fields, PostResponse::FIELDS).map_err(|name| format!("Unknown field: {}", name))?,
+            None => PostResponse::DEFAULT_FIELDS.to_vec(),
+        };
+        let include = match query.include.as_deref() {
+            Some(include) => parse_list(include, PostResponse::INCLUDES).map_err(|name| format!("Unknown include: {}", name))?,
+            None => Vec::new(),
+        };
+        Ok(PostFieldset {
+            fields,
+            assets: include.contains(&"assets"),
+            tags: include.contains(&"tags"),
+        })
+    }
+
+    /// SELECT 的欄位：一律包含 id（查詢資源用）與 `extra`
+    pub fn columns(&self, extra: &[&str]) -> String {
+        let mut columns = vec!["id"];
+        // html 由 content 渲染
+        let needed = self
+            .fields
+            .iter()
+            .map(|field| if *field == "html" { "content" } else { *field })
+            .chain(self.tags.then_some("tags"))
+            .chain(extra.iter().copied());
+        for column in needed {
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+        columns.join(", ")
+    }
+
+    /// 用於快取 key，相同回應形狀的請求得到相同的 key
+    pub fn cache_key(&self) -> String {
+        let mut include = Vec::new();
+        if self.assets {
+            include.push("assets");
+        }
+        if self.tags {
+            include.push("tags");
+        }
+        format!("{}|{}", self.fields.join(","), include.join(","))
+    }
+
+    /// 將查詢結果轉為 JSON 物件；`assets` 為各文章的資源（以 post id 為 key）
+    pub fn to_json(
+        &self,
+        row: &Row,
+        assets: &mut HashMap<i32, Vec<PostAssetResponse>>,
+    ) -> serde_json::Result<Map<String, Value>> {
+        let mut object = Map::new();
+        for field in &self.fields {
+            let value = match *field {
+                "uuid" => serde_json::to_value(row.get::<_, Uuid>("uuid"))?,
+                "toc" => row.get::<_, Json<Value>>("toc").0,
+                "word_count" | "reading_minutes" => Value::from(row.get::<_, i32>(field)),
+                "created_at" | "updated_at" => serde_json::to_value(row.get::<_, SystemTime>(field))?,
+                "html" => Value::from(render_html(row.get("content"))),
+                _ => Value::from(row.get::<_, String>(field)),
+            };
+            object.insert(field.to_string(), value);
+        }
+        if self.tags {
+            object.insert("tags".to_string(), Value::from(row.get::<_, Vec<String>>("tags")));
+        }
+        if self.assets {
+            let post_assets = assets.remove(&row.get::<_, i32>("id")).unwrap_or_default();
+            object.insert("assets".to_string(), serde_json::to_value(post_assets)?);
+        }
+        Ok(object)
+    }
+}
+
+/// 解析逗號分隔的名稱，去除重複並依 `allowed` 的順序排列；有不在 `allowed` 中的名稱時回傳該名稱
+fn parse_list(value: &str, allowed: &[&'static str]) -> Result<Vec<&'static str>, String> {
+    let requested: Vec<&str> = value.split(',').map(str::trim).filter(|name| !name.is_empty()).collect();
+    if let Some(unknown) = requested.iter().find(|name| !allowed.contains(name)) {
+        return Err(unknown.to_string());
+    }
+    Ok(allowed.iter().copied().filter(|name| requested.contains(name)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(fields: Option<&str>, include: Option<&str>) -> Result<PostFieldset, String> {
+        PostFieldset::parse(&PostFieldsQuery {
+            fields: fields.map(str::to_string),
+            include: include.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn defaults_omit_html() {
+        let fieldset = parse(None, None).unwrap();
+        assert_eq!(fieldset.fields, PostResponse::DEFAULT_FIELDS);
+        assert!(!fieldset.fields.contains(&"html"));
+        assert!(!fieldset.assets && !fieldset.tags);
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        assert_eq!(parse(Some("uuid,password"), None), Err("Unknown field: password".to_string()));
+        assert_eq!(parse(None, Some("tags,comments")), Err("Unknown include: comments".to_string()));
+        // 名稱區分大小寫
+        assert_eq!(parse(Some("UUID"), None), Err("Unknown field: UUID".to_string()));
+    }
+
+    #[test]
+    fn orders_and_deduplicates_fields() {
+        let fieldset = parse(Some(" html, uuid ,,html"), Some("tags,assets,tags")).unwrap();
+        assert_eq!(fieldset.fields, ["uuid", "html"]);
+        assert!(fieldset.assets && fieldset.tags);
+        // 空字串代表不回傳任何欄位
+        assert!(parse(Some(""), None).unwrap().fields.is_empty());
+    }
+
+    #[test]
+    fn selects_needed_columns() {
+        let fieldset = parse(Some("html,title"), Some("tags")).unwrap();
+        assert_eq!(fieldset.columns(&[]), "id, title, content, tags");
+        assert_eq!(fieldset.columns(&["created_at", "id"]), "id, title, content, tags, created_at");
+    }
+
+    #[test]
+    fn cache_key_depends_on_shape_only() {
+        let a = parse(Some("title,uuid"), Some("assets")).unwrap();
+        let b = parse(Some("uuid,title,uuid"), Some("assets")).unwrap();
+        assert_eq!(a.cache_key(), "uuid,title|assets");
+        assert_eq!(a.cache_key(), b.cache_key());
+        assert_ne!(a.cache_key(), parse(Some("uuid,title"), None).unwrap().cache_key());
+        assert_ne!(a.cache_key(), parse(Some("uuid,title"), Some("assets,tags")).unwrap().cache_key());
+    }
+}
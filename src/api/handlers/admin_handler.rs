@@ -0,0 +1,145 @@
+use actix_web::http::header;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use std::time::SystemTime;
+use uuid::Uuid;
+
+use crate::api::config::ApiConfig;
+use crate::api::metrics::timed_query;
+use crate::common::models::format_rfc3339;
+
+#[derive(Serialize)]
+struct LinkProblem {
+    url: String,
+    status: String,
+    status_code: Option<i32>,
+    error: Option<String>,
+    checked_at: String,
+    /// 最近一次檢查成功的時間，從未成功時為 null
+    last_ok_at: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PostLinkSummary {
+    uuid: Uuid,
+    title: String,
+    links: i64,
+    broken: i64,
+    rate_limited: i64,
+    last_checked_at: String,
+    problems: Vec<LinkProblem>,
+}
+
+#[derive(Serialize)]
+struct LinkCheckSummary {
+    links: i64,
+    broken: i64,
+    posts: Vec<PostLinkSummary>,
+}
+
+/// 最近一次 check-links 的結果：每篇文章的連結數、失效數與有問題的連結
+/// 需要 `Authorization: Bearer $ADMIN_TOKEN`；未設定 ADMIN_TOKEN 時此端點不存在（404）
+/// GET /api/admin/link-checks
+#[get("/api/admin/link-checks")]
+pub async fn link_checks(pool: web::Data<Pool>, config: web::Data<ApiConfig>, req: HttpRequest) -> impl Responder {
+    let Some(token) = config.admin_token.as_deref() else {
+        return HttpResponse::NotFound().finish();
+    };
+    if !is_authorized(&req, token) {
+        return HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .finish();
+    }
+
+    let client = match pool.get().await {
+        Ok(client) => client,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    let post_rows = match timed_query(
+        "link_check_summary",
+        client.query(
+            "SELECT p.id, p.uuid, p.title,
+                    COUNT(*) AS links,
+                    COUNT(*) FILTER (WHERE lc.status IN ('broken', 'error')) AS broken,
+                    COUNT(*) FILTER (WHERE lc.status = 'rate_limited') AS rate_limited,
+                    MAX(lc.checked_at) AS last_checked_at
+             FROM link_checks lc JOIN posts p ON p.id = lc.post_id
+             GROUP BY p.id
+             ORDER BY broken DESC, p.created_at DESC",
+            &[],
+        ),
+    )
+    .await
+    {
+        Ok(rows) => rows,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    let problem_rows = match timed_query(
+        "list_link_problems",
+        client.query(
+            "SELECT post_id, url, status, status_code, error, checked_at, last_ok_at
+             FROM link_checks WHERE status <> 'ok' ORDER BY url",
+            &[],
+        ),
+    )
+    .await
+    {
+        Ok(rows) => rows,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    let posts: Vec<PostLinkSummary> = post_rows
+        .iter()
+        .map(|row| {
+            let post_id: i32 = row.get("id");
+            let last_checked_at: SystemTime = row.get("last_checked_at");
+            PostLinkSummary {
+                uuid: row.get("uuid"),
+                title: row.get("title"),
+                links: row.get("links"),
+                broken: row.get("broken"),
+                rate_limited: row.get("rate_limited"),
+                last_checked_at: format_rfc3339(last_checked_at),
+                problems: problem_rows
+                    .iter()
+                    .filter(|problem| problem.get::<_, i32>("post_id") == post_id)
+                    .map(|problem| LinkProblem {
+                        url: problem.get("url"),
+                        status: problem.get("status"),
+                        status_code: problem.get("status_code"),
+                        error: problem.get("error"),
+                        checked_at: format_rfc3339(problem.get("checked_at")),
+                        last_ok_at: problem.get::<_, Option<SystemTime>>("last_ok_at").map(format_rfc3339),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    HttpResponse::Ok().json(LinkCheckSummary {
+        links: posts.iter().map(|post| post.links).sum(),
+        broken: posts.iter().map(|post| post.broken).sum(),
+        posts,
+    })
+}
+
+/// 比對 Bearer token（固定時間比較，避免由回應時間推測 token）
+fn is_authorized(req: &HttpRequest, token: &str) -> bool {
+    let Some(provided) = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    provided.len() == token.len()
+        && provided
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
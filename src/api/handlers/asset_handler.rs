@@ -1,13 +1,48 @@
 use actix_web::{get, web, HttpResponse, HttpRequest, Responder};
+use actix_web::http::header::{self, HeaderValue, TryIntoHeaderValue};
 use deadpool_postgres::Pool;
 use uuid::Uuid;
-use actix_files::NamedFile;
-use std::path::PathBuf;
+use actix_files::{Files, NamedFile};
+use std::path::{Path, PathBuf};
+use crate::api::config::ApiConfig;
+use crate::api::http_cache::{self, CacheableJson};
+use crate::api::metrics::timed_query;
+use crate::api::response_cache::ResponseCache;
+use crate::common::models::{PostAsset, PostAssetResponse};
 
-const UPLOADS_DIR: &str = "static/uploads";
+pub(crate) const UPLOADS_DIR: &str = "static/uploads";
+const STATIC_DIR: &str = "static";
+
+/// /static 的檔案服務（僅在 SERVE_STATIC 開啟時註冊），不提供目錄列表
+/// uploads 目錄一律排除，不論方法（HEAD 等）或路徑寫法（`//`、符號連結），只能透過 `get_static_upload` 取得
+pub fn static_files() -> Files {
+    Files::new("/static", STATIC_DIR).path_filter(|path, _| !is_within_uploads(path))
+}
+
+// `path` 為相對於 static 目錄的路徑；先比對第一段，再以實際路徑排除指向 uploads 的符號連結
+fn is_within_uploads(path: &Path) -> bool {
+    let uploads = Path::new(UPLOADS_DIR);
+    if Path::new(STATIC_DIR).join(path).starts_with(uploads) {
+        return true;
+    }
+    match (Path::new(STATIC_DIR).join(path).canonicalize(), uploads.canonicalize()) {
+        (Ok(full_path), Ok(root)) => full_path.starts_with(root),
+        // 不存在的檔案交由 Files 回應 404
+        _ => false,
+    }
+}
 
 /// 透過 asset UUID 取得檔案
 /// GET /api/assets/{uuid}
+#[utoipa::path(
+    tag = "assets",
+    params(("uuid" = Uuid, Path, description = "資源 UUID")),
+    responses(
+        (status = 200, description = "資源檔案，Content-Type 為下載時記錄的類型"),
+        (status = 304, description = "檔案未變更"),
+        (status = 404, description = "找不到資源或檔案"),
+    )
+)]
 #[get("/api/assets/{uuid}")]
 pub async fn get_asset(
     pool: web::Data<Pool>,
@@ -16,63 +51,150 @@ pub async fn get_asset(
 ) -> impl Responder {
     let client = match pool.get().await {
         Ok(client) => client,
-        Err(_) => return HttpResponse::InternalServerError().finish().into(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
     };
 
     // 從資料庫查詢 asset 資訊
-    let row = match client
-        .query_one(
+    let row = match timed_query(
+        "get_asset",
+        client.query_one(
             "SELECT file_path, content_type FROM post_assets WHERE asset_uuid = $1",
             &[&uuid.into_inner()],
-        )
-        .await
+        ),
+    )
+    .await
     {
         Ok(row) => row,
-        Err(_) => return HttpResponse::NotFound().body("Asset not found").into(),
+        Err(_) => return HttpResponse::NotFound().body("Asset not found"),
     };
 
     let file_path: String = row.get("file_path");
     let content_type: Option<String> = row.get("content_type");
+    serve_asset_file(&req, &file_path, content_type)
+}
 
-    // 建構完整的檔案路徑
-    let full_path = PathBuf::from(UPLOADS_DIR).join(&file_path);
+/// 以原始路徑取得上傳的檔案（僅在 SERVE_STATIC 開啟時註冊）
+/// 只提供資料庫中仍有文章引用的檔案，孤立或已刪除文章的檔案一律 404
+/// GET /static/uploads/{path}
+#[get("/static/uploads/{path:.*}")]
+pub async fn get_static_upload(
+    pool: web::Data<Pool>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> impl Responder {
+    let client = match pool.get().await {
+        Ok(client) => client,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
 
-    // 檢查檔案是否存在
-    if !full_path.exists() {
-        return HttpResponse::NotFound().body("File not found on disk").into();
-    }
+    let row = match timed_query(
+        "get_static_upload",
+        client.query_opt(
+            "SELECT a.file_path, a.content_type FROM post_assets a
+             JOIN posts p ON p.id = a.post_id
+             WHERE a.file_path = $1
+             LIMIT 1",
+            &[&path.into_inner()],
+        ),
+    )
+    .await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => return HttpResponse::NotFound().body("Asset not found"),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    let file_path: String = row.get("file_path");
+    let content_type: Option<String> = row.get("content_type");
+    serve_asset_file(&req, &file_path, content_type)
+}
+
+// SVG 與 HTML 快照可內嵌 script，直接開啟時以 CSP 禁止執行任何內容並隔離 origin
+const SANDBOX_CONTENT_SECURITY_POLICY: &str = "default-src 'none'; style-src 'unsafe-inline'; sandbox";
+
+/// 回傳 uploads 目錄中的檔案，附上快取與安全性標頭
+fn serve_asset_file(req: &HttpRequest, file_path: &str, content_type: Option<String>) -> HttpResponse {
+    // 建構完整的檔案路徑；解析 `..` 與符號連結後必須仍在 uploads 目錄內
+    let full_path = match confine_to_uploads(file_path) {
+        Some(path) => path,
+        None => return HttpResponse::NotFound().body("File not found on disk"),
+    };
 
     // 返回檔案
     match NamedFile::open(&full_path) {
         Ok(mut file) => {
-            // 如果有 content_type，設定它
+            // 如果有 content_type，設定它；否則沿用依副檔名判斷的類型
             if let Some(ct) = content_type {
                 file = file.set_content_type(
                     ct.parse::<mime::Mime>()
                         .unwrap_or(mime::APPLICATION_OCTET_STREAM)
                 );
             }
-            file.into_response(&req)
+            let content_type = file.content_type();
+            let is_active = (content_type.type_() == mime::IMAGE && content_type.subtype() == mime::SVG)
+                || (content_type.type_() == mime::TEXT && content_type.subtype() == mime::HTML)
+                || content_type.subtype() == "xhtml";
+
+            // NamedFile 會處理 ETag / Last-Modified 與條件式請求
+            let mut response = file.into_response(req);
+            let headers = response.headers_mut();
+            if let Ok(value) = http_cache::immutable_cache_control().try_into_value() {
+                headers.insert(header::CACHE_CONTROL, value);
+            }
+            headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+            if is_active {
+                headers.insert(
+                    header::CONTENT_SECURITY_POLICY,
+                    HeaderValue::from_static(SANDBOX_CONTENT_SECURITY_POLICY),
+                );
+            }
+            response
         }
-        Err(_) => HttpResponse::InternalServerError().body("Failed to read file").into(),
+        Err(_) => HttpResponse::InternalServerError().body("Failed to read file"),
     }
 }
 
+/// 回傳 uploads 目錄內檔案的實際路徑；檔案不存在或位於 uploads 目錄之外時回傳 None
+fn confine_to_uploads(file_path: &str) -> Option<PathBuf> {
+    let root = Path::new(UPLOADS_DIR).canonicalize().ok()?;
+    let full_path = root.join(file_path).canonicalize().ok()?;
+    (full_path.starts_with(&root) && full_path.is_file()).then_some(full_path)
+}
+
 /// 取得特定 post 的所有 assets（可選功能）
 /// GET /api/posts/{uuid}/assets
+#[utoipa::path(
+    tag = "assets",
+    params(("uuid" = Uuid, Path, description = "文章 UUID")),
+    responses(
+        (status = 200, description = "文章的資源列表", body = Vec<PostAssetResponse>),
+        (status = 304, description = "內容未變更（If-None-Match）"),
+        (status = 404, description = "找不到文章"),
+    )
+)]
 #[get("/api/posts/{uuid}/assets")]
 pub async fn get_post_assets(
     pool: web::Data<Pool>,
+    config: web::Data<ApiConfig>,
+    cache: web::Data<ResponseCache>,
     uuid: web::Path<Uuid>,
+    req: HttpRequest,
 ) -> impl Responder {
+    let uuid = uuid.into_inner();
+    let base_url = config.base_url(&req);
+    let cache_key = ResponseCache::post_assets_key(&uuid, &base_url);
+    if let Some(json) = cache.get(&cache_key) {
+        return json.respond(&req, config.post_max_age);
+    }
+    let generation = cache.generation();
+
     let client = match pool.get().await {
         Ok(client) => client,
         Err(_) => return HttpResponse::InternalServerError().finish(),
     };
 
     // 先取得 post_id
-    let post_row = match client
-        .query_one("SELECT id FROM posts WHERE uuid = $1", &[&uuid.into_inner()])
+    let post_row = match timed_query("get_post_id", client.query_one("SELECT id FROM posts WHERE uuid = $1", &[&uuid]))
         .await
     {
         Ok(row) => row,
@@ -82,32 +204,30 @@ pub async fn get_post_assets(
     let post_id: i32 = post_row.get("id");
 
     // 取得所有 assets
-    let rows = match client
-        .query(
-            "SELECT asset_uuid, original_url, content_type, file_size, created_at 
-             FROM post_assets 
-             WHERE post_id = $1 
-             ORDER BY created_at",
+    let rows = match timed_query(
+        "list_post_assets",
+        client.query(
+            "SELECT * FROM post_assets WHERE post_id = $1 ORDER BY created_at",
             &[&post_id],
-        )
-        .await
+        ),
+    )
+    .await
     {
         Ok(rows) => rows,
         Err(_) => return HttpResponse::InternalServerError().finish(),
     };
 
-    let assets: Vec<serde_json::Value> = rows
-        .iter()
-        .map(|row| {
-            serde_json::json!({
-                "asset_uuid": row.get::<_, Uuid>("asset_uuid"),
-                "original_url": row.get::<_, String>("original_url"),
-                "content_type": row.get::<_, Option<String>>("content_type"),
-                "file_size": row.get::<_, Option<i64>>("file_size"),
-                "url": format!("/api/assets/{}", row.get::<_, Uuid>("asset_uuid")),
-            })
-        })
+    let assets: Vec<PostAssetResponse> = rows
+        .into_iter()
+        .map(|row| PostAssetResponse::from_asset(PostAsset::from(row), &base_url))
         .collect();
 
-    HttpResponse::Ok().json(assets)
+    match CacheableJson::new(&assets, None) {
+        Ok(json) => {
+            let response = json.respond(&req, config.post_max_age);
+            cache.insert(cache_key, json, generation);
+            response
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
 }
\ No newline at end of file
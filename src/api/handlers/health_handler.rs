@@ -0,0 +1,132 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::api::handlers::asset_handler::UPLOADS_DIR;
+use crate::api::shutdown::ShutdownState;
+use crate::common::db::SCHEMA_VERSION;
+
+// 單一檢查的逾時，避免資料庫卡住時 readiness 請求一起卡住
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    Ok,
+    Fail,
+}
+
+#[derive(Serialize)]
+struct CheckResult {
+    status: Status,
+    latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct HealthReport {
+    status: Status,
+    checks: BTreeMap<&'static str, CheckResult>,
+}
+
+/// 存活檢查：行程能回應請求即可，不檢查外部依賴
+/// GET /healthz
+#[get("/healthz")]
+pub async fn liveness() -> impl Responder {
+    HttpResponse::Ok().json(HealthReport {
+        status: Status::Ok,
+        checks: BTreeMap::new(),
+    })
+}
+
+/// 就緒檢查：資料庫連線、資料庫結構版本與資源目錄，任一失敗回傳 503
+/// 伺服器關閉中時一律回報失敗
+/// GET /readyz
+#[get("/readyz")]
+pub async fn readiness(pool: web::Data<Pool>, shutdown: web::Data<ShutdownState>) -> impl Responder {
+    let mut checks = BTreeMap::new();
+    checks.insert("shutdown", run_check(check_shutdown(&shutdown)).await);
+    checks.insert("database", run_check(check_database(&pool)).await);
+    checks.insert("schema", run_check(check_schema(&pool)).await);
+    checks.insert("asset_store", run_check(check_asset_store()).await);
+
+    let ready = checks.values().all(|check| matches!(check.status, Status::Ok));
+    let report = HealthReport {
+        status: if ready { Status::Ok } else { Status::Fail },
+        checks,
+    };
+    if ready {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+async fn run_check<F>(check: F) -> CheckResult
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let started = Instant::now();
+    let result = match actix_web::rt::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    match result {
+        Ok(()) => CheckResult {
+            status: Status::Ok,
+            latency_ms,
+            error: None,
+        },
+        Err(error) => CheckResult {
+            status: Status::Fail,
+            latency_ms,
+            error: Some(error),
+        },
+    }
+}
+
+async fn check_shutdown(shutdown: &ShutdownState) -> Result<(), String> {
+    if shutdown.is_draining() {
+        Err("server is shutting down".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+async fn check_database(pool: &Pool) -> Result<(), String> {
+    let client = pool.get().await.map_err(|e| e.to_string())?;
+    client.execute("SELECT 1", &[]).await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+async fn check_schema(pool: &Pool) -> Result<(), String> {
+    let client = pool.get().await.map_err(|e| e.to_string())?;
+    let row = client
+        .query_opt("SELECT MAX(version) AS version FROM schema_version", &[])
+        .await
+        .map_err(|e| e.to_string())?;
+    let version: Option<i32> = row.and_then(|row| row.get("version"));
+    match version {
+        Some(SCHEMA_VERSION) => Ok(()),
+        Some(version) => Err(format!("schema version {} does not match expected {}", version, SCHEMA_VERSION)),
+        None => Err("schema version is not recorded; run init-db".to_string()),
+    }
+}
+
+// 在資源目錄寫入並刪除一個暫存檔，確認目錄存在且可寫入
+async fn check_asset_store() -> Result<(), String> {
+    let probe = PathBuf::from(UPLOADS_DIR).join(format!(".readyz-{}", Uuid::new_v4()));
+    tokio::fs::write(&probe, b"")
+        .await
+        .map_err(|e| format!("{} is not writable: {}", UPLOADS_DIR, e))?;
+    tokio::fs::remove_file(&probe).await.map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::api::config::ApiConfig;
+use crate::api::http_cache::CacheableJson;
+use crate::common::highlight::{self, Language};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ThemeQuery {
+    /// 配色名稱（見 /api/highlight/themes），未指定時使用 HIGHLIGHT_THEME
+    pub theme: Option<String>,
+}
+
+/// 程式碼區塊支援標示的語言；code fence 可使用語言的任一 token
+/// GET /api/highlight/languages
+#[utoipa::path(
+    tag = "highlight",
+    responses(
+        (status = 200, description = "依名稱排序的語言", body = Vec<Language>),
+        (status = 304, description = "內容未變更（If-None-Match）"),
+    )
+)]
+#[get("/api/highlight/languages")]
+pub async fn get_languages(config: web::Data<ApiConfig>, req: HttpRequest) -> impl Responder {
+    match CacheableJson::new(&highlight::languages(), None) {
+        Ok(json) => json.respond(&req, config.post_max_age),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// 可用的配色名稱
+/// GET /api/highlight/themes
+#[utoipa::path(
+    tag = "highlight",
+    responses(
+        (status = 200, description = "配色名稱", body = Vec<String>),
+        (status = 304, description = "內容未變更（If-None-Match）"),
+    )
+)]
+#[get("/api/highlight/themes")]
+pub async fn get_themes(config: web::Data<ApiConfig>, req: HttpRequest) -> impl Responder {
+    match CacheableJson::new(&highlight::themes(), None) {
+        Ok(json) => json.respond(&req, config.post_max_age),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// 文章 HTML 中程式碼區塊（`<pre class="hl-code">`）的樣式
+/// GET /api/highlight.css?theme=InspiredGitHub
+#[utoipa::path(
+    tag = "highlight",
+    params(ThemeQuery),
+    responses(
+        (status = 200, description = "CSS", content_type = "text/css"),
+        (status = 404, description = "找不到配色"),
+    )
+)]
+#[get("/api/highlight.css")]
+pub async fn get_theme_css(config: web::Data<ApiConfig>, query: web::Query<ThemeQuery>) -> impl Responder {
+    let theme = query.theme.as_deref().unwrap_or(&config.highlight_theme);
+    match highlight::theme_css(theme) {
+        Some(css) => HttpResponse::Ok()
+            .content_type("text/css; charset=utf-8")
+            .insert_header(CacheControl(vec![
+                CacheDirective::Public,
+                CacheDirective::MaxAge(config.post_max_age as u32),
+            ]))
+            .body(css),
+        None => HttpResponse::NotFound().body("Theme not found"),
+    }
+}
@@ -1,2 +1,5 @@
 pub mod post_handler;
-pub mod asset_handler;
\ No newline at end of file
+pub mod asset_handler;
+pub mod health_handler;
+pub mod admin_handler;
+pub mod highlight_handler;
\ No newline at end of file
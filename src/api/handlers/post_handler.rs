@@ -1,15 +1,50 @@
-use actix_web::{get, web, HttpResponse, Responder};
-use deadpool_postgres::Pool;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use deadpool_postgres::{Client, Pool};
+use std::collections::HashMap;
+use std::time::SystemTime;
 use uuid::Uuid;
-use crate::common::models::{Post, PostResponse, Pagination};
+use crate::api::config::ApiConfig;
+use crate::api::fieldset::PostFieldset;
+use crate::api::http_cache::CacheableJson;
+use crate::api::metrics::timed_query;
+use crate::api::response_cache::ResponseCache;
+use crate::common::models::{
+    ArchivedCopy, Pagination, PostAsset, PostAssetResponse, PostFieldsQuery, PostResponse, ReferenceResponse,
+};
+use crate::common::render::external_links;
 
 /// 取得所有文章列表
-/// GET /api/posts?page=1&limit=10
+/// GET /api/posts?page=1&limit=10&fields=uuid,title,excerpt&include=tags
+#[utoipa::path(
+    tag = "posts",
+    params(Pagination, PostFieldsQuery),
+    responses(
+        (status = 200, description = "依建立時間新到舊排列的文章；指定 fields 時只包含這些欄位", body = Vec<PostResponse>),
+        (status = 304, description = "內容未變更（If-None-Match）"),
+        (status = 400, description = "fields 或 include 包含未知的名稱"),
+    )
+)]
 #[get("/api/posts")]
 pub async fn get_posts(
     pool: web::Data<Pool>,
+    config: web::Data<ApiConfig>,
+    cache: web::Data<ResponseCache>,
     pagination: web::Query<Pagination>,
+    query: web::Query<PostFieldsQuery>,
+    req: HttpRequest,
 ) -> impl Responder {
+    let pagination = pagination.into_inner().clamped();
+    let fieldset = match PostFieldset::parse(&query) {
+        Ok(fieldset) => fieldset,
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
+    let base_url = config.base_url(&req);
+    let cache_key = ResponseCache::list_key(pagination.page, pagination.limit, &fieldset.cache_key(), &base_url);
+    if let Some(json) = cache.get(&cache_key) {
+        return json.respond(&req, config.post_max_age);
+    }
+    let generation = cache.generation();
+
     let client = match pool.get().await {
         Ok(client) => client,
         Err(_) => return HttpResponse::InternalServerError().finish(),
@@ -17,53 +52,228 @@ pub async fn get_posts(
 
     let offset = (pagination.page - 1) * pagination.limit;
 
-    let rows = match client
-        .query(
-            "SELECT id, uuid, title, content, created_at FROM posts ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+    let rows = match timed_query(
+        "list_posts",
+        client.query(
+            &format!(
+                "SELECT {} FROM posts ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+                fieldset.columns(&[])
+            ),
             &[&(pagination.limit as i64), &(offset as i64)],
-        )
-        .await
+        ),
+    )
+    .await
     {
         Ok(rows) => rows,
         Err(_) => return HttpResponse::InternalServerError().finish(),
     };
 
-    let posts: Vec<PostResponse> = rows
-        .into_iter()
-        .map(|row| {
-            let post = Post::from(row);
-            PostResponse::from(post)
-        })
-        .collect();
+    let mut assets = if fieldset.assets {
+        let post_ids: Vec<i32> = rows.iter().map(|row| row.get("id")).collect();
+        match load_assets(&client, &post_ids, &base_url).await {
+            Ok(assets) => assets,
+            Err(_) => return HttpResponse::InternalServerError().finish(),
+        }
+    } else {
+        HashMap::new()
+    };
 
-    HttpResponse::Ok().json(posts)
+    let posts: Result<Vec<_>, _> = rows.iter().map(|row| fieldset.to_json(row, &mut assets)).collect();
+    let Ok(posts) = posts else {
+        return HttpResponse::InternalServerError().finish();
+    };
+
+    // 列表不提供 Last-Modified：刪除文章不會反映在 updated_at 上，只以 ETag 驗證
+    match CacheableJson::new(&posts, None) {
+        Ok(json) => {
+            let response = json.respond(&req, config.post_max_age);
+            cache.insert(cache_key, json, generation);
+            response
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
 }
 
 /// 透過 UUID 取得單一文章
-/// GET /api/posts/{uuid}
+/// GET /api/posts/{uuid}?fields=title,content&include=assets
+#[utoipa::path(
+    tag = "posts",
+    params(("uuid" = Uuid, Path, description = "文章 UUID"), PostFieldsQuery),
+    responses(
+        (status = 200, description = "文章內容；指定 fields 時只包含這些欄位", body = PostResponse),
+        (status = 304, description = "內容未變更（If-None-Match / If-Modified-Since）"),
+        (status = 400, description = "fields 或 include 包含未知的名稱"),
+        (status = 404, description = "找不到文章"),
+    )
+)]
 #[get("/api/posts/{uuid}")]
 pub async fn get_post_by_uuid(
     pool: web::Data<Pool>,
+    config: web::Data<ApiConfig>,
+    cache: web::Data<ResponseCache>,
     uuid: web::Path<Uuid>,
+    query: web::Query<PostFieldsQuery>,
+    req: HttpRequest,
 ) -> impl Responder {
+    let uuid = uuid.into_inner();
+    let fieldset = match PostFieldset::parse(&query) {
+        Ok(fieldset) => fieldset,
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
+    let base_url = config.base_url(&req);
+    let cache_key = ResponseCache::post_key(&uuid, &fieldset.cache_key(), &base_url);
+    if let Some(json) = cache.get(&cache_key) {
+        return json.respond(&req, config.post_max_age);
+    }
+    let generation = cache.generation();
+
     let client = match pool.get().await {
         Ok(client) => client,
         Err(_) => return HttpResponse::InternalServerError().finish(),
     };
 
-    let row = match client
-        .query_one(
-            "SELECT id, uuid, title, content, created_at FROM posts WHERE uuid = $1",
-            &[&uuid.into_inner()],
-        )
-        .await
+    // updated_at 用於 Last-Modified，不論是否在 fields 中都要取得
+    let row = match timed_query(
+        "get_post",
+        client.query_one(
+            &format!("SELECT {} FROM posts WHERE uuid = $1", fieldset.columns(&["updated_at"])),
+            &[&uuid],
+        ),
+    )
+    .await
     {
         Ok(row) => row,
         Err(_) => return HttpResponse::NotFound().body("Post not found"),
     };
 
-    let post = Post::from(row);
-    let response = PostResponse::from(post);
-    
-    HttpResponse::Ok().json(response)
-}
\ No newline at end of file
+    let mut assets = if fieldset.assets {
+        match load_assets(&client, &[row.get("id")], &base_url).await {
+            Ok(assets) => assets,
+            Err(_) => return HttpResponse::InternalServerError().finish(),
+        }
+    } else {
+        HashMap::new()
+    };
+
+    let Ok(post) = fieldset.to_json(&row, &mut assets) else {
+        return HttpResponse::InternalServerError().finish();
+    };
+    let updated_at: SystemTime = row.get("updated_at");
+
+    match CacheableJson::new(&post, Some(updated_at)) {
+        Ok(json) => {
+            let response = json.respond(&req, config.post_max_age);
+            cache.insert(cache_key, json, generation);
+            response
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// 一次查詢多篇文章的資源，依 post id 分組
+async fn load_assets(
+    client: &Client,
+    post_ids: &[i32],
+    base_url: &str,
+) -> Result<HashMap<i32, Vec<PostAssetResponse>>, tokio_postgres::Error> {
+    let rows = timed_query(
+        "list_posts_assets",
+        client.query(
+            "SELECT * FROM post_assets WHERE post_id = ANY($1) ORDER BY created_at",
+            &[&post_ids],
+        ),
+    )
+    .await?;
+
+    let mut assets: HashMap<i32, Vec<PostAssetResponse>> = HashMap::new();
+    for asset in rows.into_iter().map(PostAsset::from) {
+        assets
+            .entry(asset.post_id)
+            .or_default()
+            .push(PostAssetResponse::from_asset(asset, base_url));
+    }
+    Ok(assets)
+}
+
+/// 取得文章中的外部連結與匯入時保存的快照
+/// GET /api/posts/{uuid}/references
+#[utoipa::path(
+    tag = "posts",
+    params(("uuid" = Uuid, Path, description = "文章 UUID")),
+    responses(
+        (status = 200, description = "依出現順序排列的外部連結；沒有快照的連結 archive 為 null", body = Vec<ReferenceResponse>),
+        (status = 304, description = "內容未變更（If-None-Match）"),
+        (status = 404, description = "找不到文章"),
+    )
+)]
+#[get("/api/posts/{uuid}/references")]
+pub async fn get_post_references(
+    pool: web::Data<Pool>,
+    config: web::Data<ApiConfig>,
+    cache: web::Data<ResponseCache>,
+    uuid: web::Path<Uuid>,
+    req: HttpRequest,
+) -> impl Responder {
+    let uuid = uuid.into_inner();
+    let base_url = config.base_url(&req);
+    let cache_key = ResponseCache::post_references_key(&uuid, &base_url);
+    if let Some(json) = cache.get(&cache_key) {
+        return json.respond(&req, config.post_max_age);
+    }
+    let generation = cache.generation();
+
+    let client = match pool.get().await {
+        Ok(client) => client,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    let post_row = match timed_query(
+        "get_post_content",
+        client.query_one("SELECT id, content FROM posts WHERE uuid = $1", &[&uuid]),
+    )
+    .await
+    {
+        Ok(row) => row,
+        Err(_) => return HttpResponse::NotFound().body("Post not found"),
+    };
+    let post_id: i32 = post_row.get("id");
+    let content: String = post_row.get("content");
+
+    let rows = match timed_query(
+        "list_post_archives",
+        client.query(
+            "SELECT * FROM post_assets WHERE post_id = $1 AND kind = 'archive' ORDER BY created_at",
+            &[&post_id],
+        ),
+    )
+    .await
+    {
+        Ok(rows) => rows,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    let mut archives: HashMap<String, PostAsset> = rows
+        .into_iter()
+        .map(PostAsset::from)
+        .map(|asset| (asset.original_url.clone(), asset))
+        .collect();
+    let references: Vec<ReferenceResponse> = external_links(&content)
+        .into_iter()
+        .map(|link| ReferenceResponse {
+            archive: archives
+                .remove(&link.url)
+                .map(|asset| ArchivedCopy::from_asset(asset, &base_url)),
+            url: link.url,
+            text: link.text,
+        })
+        .collect();
+
+    match CacheableJson::new(&references, None) {
+        Ok(json) => {
+            let response = json.respond(&req, config.post_max_age);
+            cache.insert(cache_key, json, generation);
+            response
+        }
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
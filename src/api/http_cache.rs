@@ -0,0 +1,88 @@
+use actix_web::http::header::{
+    self, CacheControl, CacheDirective, EntityTag, HttpDate, IfModifiedSince, IfNoneMatch, LastModified, ETag,
+};
+use actix_web::web::Bytes;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// asset 以 UUID 定址，內容不會變動，可長期快取
+pub const IMMUTABLE_MAX_AGE: u32 = 31_536_000;
+
+/// 已序列化的 JSON 回應與其快取驗證資訊（強 ETag、Last-Modified）
+#[derive(Clone, Debug)]
+pub struct CacheableJson {
+    pub body: Bytes,
+    pub etag: EntityTag,
+    pub last_modified: Option<SystemTime>,
+}
+
+impl CacheableJson {
+    pub fn new<T: Serialize>(value: &T, last_modified: Option<SystemTime>) -> serde_json::Result<Self> {
+        let body = serde_json::to_vec(value)?;
+        let digest = Sha256::digest(&body);
+        Ok(CacheableJson {
+            etag: EntityTag::new_strong(format!("{:x}", digest)[..32].to_string()),
+            body: Bytes::from(body),
+            // HTTP 日期只精確到秒
+            last_modified: last_modified.map(truncate_to_secs),
+        })
+    }
+
+    /// 依照條件式請求標頭回傳 304 或完整內容
+    pub fn respond(&self, req: &HttpRequest, max_age: u64) -> HttpResponse {
+        let cache_control = CacheControl(vec![CacheDirective::Public, CacheDirective::MaxAge(max_age as u32)]);
+
+        if is_not_modified(req, &self.etag, self.last_modified) {
+            let mut response = HttpResponse::NotModified();
+            response.insert_header(ETag(self.etag.clone())).insert_header(cache_control);
+            if let Some(last_modified) = self.last_modified {
+                response.insert_header(LastModified(HttpDate::from(last_modified)));
+            }
+            return response.finish();
+        }
+
+        let mut response = HttpResponse::Ok();
+        response
+            .content_type(mime::APPLICATION_JSON)
+            .insert_header(ETag(self.etag.clone()))
+            .insert_header(cache_control);
+        if let Some(last_modified) = self.last_modified {
+            response.insert_header(LastModified(HttpDate::from(last_modified)));
+        }
+        response.body(self.body.clone())
+    }
+}
+
+/// If-None-Match 優先於 If-Modified-Since（RFC 9110 §13.2.2）
+fn is_not_modified(req: &HttpRequest, etag: &EntityTag, last_modified: Option<SystemTime>) -> bool {
+    if req.headers().contains_key(header::IF_NONE_MATCH) {
+        return match req.get_header::<IfNoneMatch>() {
+            Some(IfNoneMatch::Any) => true,
+            Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(etag)),
+            None => false,
+        };
+    }
+
+    match (req.get_header::<IfModifiedSince>(), last_modified) {
+        (Some(IfModifiedSince(since)), Some(last_modified)) => last_modified <= SystemTime::from(since),
+        _ => false,
+    }
+}
+
+fn truncate_to_secs(time: SystemTime) -> SystemTime {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => UNIX_EPOCH + Duration::from_secs(duration.as_secs()),
+        Err(_) => time,
+    }
+}
+
+/// asset 回應使用的 Cache-Control
+pub fn immutable_cache_control() -> CacheControl {
+    CacheControl(vec![
+        CacheDirective::Public,
+        CacheDirective::MaxAge(IMMUTABLE_MAX_AGE),
+        CacheDirective::Extension("immutable".to_string(), None),
+    ])
+}
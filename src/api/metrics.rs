@@ -0,0 +1,150 @@
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{get, web, Error, HttpResponse, Responder};
+use deadpool_postgres::Pool;
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use crate::common::telemetry::traced_query;
+
+// 資源檔案的路由，用於統計傳送的位元組數
+const ASSET_ROUTE: &str = "/api/assets/{uuid}";
+
+/// API 伺服器的 Prometheus 指標
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    asset_bytes_served: IntCounter,
+    response_cache_requests: IntCounterVec,
+    db_query_duration: HistogramVec,
+    pool_size: IntGauge,
+    pool_available: IntGauge,
+    pool_waiting: IntGauge,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(|| Metrics::new().expect("invalid metric definitions"));
+
+/// 全域指標，handler、快取與 middleware 共用
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some("journal".to_string()), None)?;
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route, method and status"),
+            &["route", "method", "status"],
+        )?;
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route and method"),
+            &["route", "method"],
+        )?;
+        let asset_bytes_served = IntCounter::new("asset_bytes_served_total", "Bytes of asset files served")?;
+        let response_cache_requests = IntCounterVec::new(
+            Opts::new("response_cache_requests_total", "Response cache lookups by result (hit / miss)"),
+            &["result"],
+        )?;
+        let db_query_duration = HistogramVec::new(
+            HistogramOpts::new("db_query_duration_seconds", "Database query latency by query")
+                .buckets(vec![0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0]),
+            &["query"],
+        )?;
+        let pool_size = IntGauge::new("db_pool_size", "Connections currently held by the pool")?;
+        let pool_available = IntGauge::new("db_pool_available", "Idle connections in the pool")?;
+        let pool_waiting = IntGauge::new("db_pool_waiting", "Requests waiting for a connection")?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_request_duration.clone()))?;
+        registry.register(Box::new(asset_bytes_served.clone()))?;
+        registry.register(Box::new(response_cache_requests.clone()))?;
+        registry.register(Box::new(db_query_duration.clone()))?;
+        registry.register(Box::new(pool_size.clone()))?;
+        registry.register(Box::new(pool_available.clone()))?;
+        registry.register(Box::new(pool_waiting.clone()))?;
+
+        Ok(Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            asset_bytes_served,
+            response_cache_requests,
+            db_query_duration,
+            pool_size,
+            pool_available,
+            pool_waiting,
+        })
+    }
+
+    pub fn record_cache_lookup(&self, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        self.response_cache_requests.with_label_values(&[result]).inc();
+    }
+
+    fn record_pool(&self, pool: &Pool) {
+        let status = pool.status();
+        self.pool_size.set(status.size as i64);
+        self.pool_available.set(status.available as i64);
+        self.pool_waiting.set(status.waiting as i64);
+    }
+}
+
+/// 執行資料庫查詢，除了 `telemetry::traced_query` 的 span 之外也記錄到耗時指標
+pub async fn timed_query<F: Future>(query: &str, future: F) -> F::Output {
+    let timer = metrics().db_query_duration.with_label_values(&[query]).start_timer();
+    let output = traced_query(query, future).await;
+    timer.observe_duration();
+    output
+}
+
+/// 記錄每個請求的路由、狀態碼與耗時
+/// 路由使用註冊時的 pattern（例如 /api/posts/{uuid}），避免 UUID 造成標籤數量爆增
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().to_string();
+    let started = Instant::now();
+
+    let response = next.call(req).await?;
+
+    let metrics = metrics();
+    let status = response.status();
+    metrics
+        .http_requests
+        .with_label_values(&[route.as_str(), method.as_str(), status.as_str()])
+        .inc();
+    metrics
+        .http_request_duration
+        .with_label_values(&[route.as_str(), method.as_str()])
+        .observe(started.elapsed().as_secs_f64());
+
+    if route == ASSET_ROUTE
+        && status.is_success()
+        && let BodySize::Sized(bytes) = response.response().body().size()
+    {
+        metrics.asset_bytes_served.inc_by(bytes);
+    }
+
+    Ok(response)
+}
+
+/// Prometheus 指標（text exposition format）
+/// GET /metrics
+#[get("/metrics")]
+pub async fn metrics_endpoint(pool: web::Data<Pool>) -> impl Responder {
+    let metrics = metrics();
+    metrics.record_pool(&pool);
+
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    match encoder.encode(&metrics.registry.gather(), &mut buffer) {
+        Ok(()) => HttpResponse::Ok().content_type(encoder.format_type()).body(buffer),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
@@ -1 +1,105 @@
-pub mod handlers;
\ No newline at end of file
+pub mod config;
+pub mod fieldset;
+pub mod handlers;
+pub mod http_cache;
+pub mod metrics;
+pub mod openapi;
+pub mod rate_limit;
+pub mod request_id;
+pub mod response_cache;
+pub mod shutdown;
+
+use actix_cors::Cors;
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::from_fn;
+use actix_web::{web, App, Error};
+use utoipa::OpenApi;
+use utoipa_actix_web::AppExt;
+use utoipa_actix_web::service_config::ServiceConfig;
+
+use crate::api::handlers::{admin_handler, asset_handler, health_handler, highlight_handler, post_handler};
+
+/// 註冊所有 API 路由；加入 OpenAPI 文件的 handler 都必須在這裡註冊
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(post_handler::get_posts)
+        .service(post_handler::get_post_by_uuid)
+        .service(post_handler::get_post_references)
+        .service(asset_handler::get_asset)
+        .service(asset_handler::get_post_assets)
+        .service(highlight_handler::get_languages)
+        .service(highlight_handler::get_themes)
+        .service(highlight_handler::get_theme_css);
+}
+
+/// `app()` 中刻意不列入 OpenAPI 文件的路由：文件本身、監控、健康檢查與管理端點
+pub const UNDOCUMENTED_ROUTES: &[&str] = &[
+    "/api/openapi.json",
+    "/api/docs",
+    "/metrics",
+    "/healthz",
+    "/readyz",
+    "/api/admin/link-checks",
+];
+
+/// 建立包含 API 路由、/api/openapi.json、/api/docs、/metrics、健康檢查與管理端點的 App
+/// OpenAPI 文件由實際註冊的路由產生，不會與路由不一致
+pub fn app() -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<BoxBody>,
+        Error = Error,
+        InitError = (),
+    >,
+> {
+    let (app, spec) = App::new()
+        .into_utoipa_app()
+        .openapi(openapi::ApiDoc::openapi())
+        .configure(configure)
+        .split_for_parts();
+
+    app.app_data(web::Data::new(spec))
+        .service(openapi::openapi_json)
+        .service(openapi::docs)
+        .service(metrics::metrics_endpoint)
+        .service(health_handler::liveness)
+        .service(health_handler::readiness)
+        .service(admin_handler::link_checks)
+}
+
+/// 前端的 CORS 設定：只允許本機開發用的 origin 與 GET
+pub fn cors() -> Cors {
+    Cors::default()
+        .allowed_origin("http://localhost:3000")     // 允許前端的 origin
+        .allowed_origin("http://localhost:5173")     // Vite 預設 port
+        .allowed_origin("http://127.0.0.1:3000")
+        .allowed_origin("http://127.0.0.1:5173")
+        .allowed_methods(vec!["GET"])                // 只允許 GET
+        .allowed_headers(vec![
+            header::AUTHORIZATION,
+            header::ACCEPT,
+            header::CONTENT_TYPE,
+        ])
+        .expose_headers(vec![request_id::REQUEST_ID_HEADER])
+        .max_age(3600)                               // preflight 快取 1 小時
+}
+
+/// 加上 middleware 的 `app()`，由內而外為流量限制、指標、request ID 與 CORS
+/// CORS 在最外層，流量限制回應的 429 也帶有 CORS 標頭，瀏覽器端才讀得到
+pub fn app_with_middleware() -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = Error,
+        InitError = (),
+    >,
+> {
+    app()
+        .wrap(from_fn(rate_limit::limit_requests))    // 每個 IP 的流量限制
+        .wrap(from_fn(metrics::track_requests))       // 請求數與延遲指標
+        .wrap(from_fn(request_id::trace_requests))    // X-Request-Id 與 access log
+        .wrap(cors())
+}
@@ -0,0 +1,45 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use utoipa::OpenApi;
+
+/// OpenAPI 文件的基本資訊；路徑與 schema 由 `api::app` 註冊的 handler 自動收集
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Journal API", description = "唯讀的文章與資源 API，寫入請使用 CLI"),
+    tags(
+        (name = "posts", description = "文章"),
+        (name = "assets", description = "文章引用的資源檔案"),
+        (name = "highlight", description = "程式碼區塊的語法標示"),
+    )
+)]
+pub struct ApiDoc;
+
+// Redoc 互動文件頁面，從 CDN 載入
+const DOCS_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <title>Journal API</title>
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+</head>
+<body>
+    <redoc spec-url="/api/openapi.json"></redoc>
+    <script src="https://cdn.redoc.ly/redoc/latest/bundles/redoc.standalone.js"></script>
+</body>
+</html>
+"#;
+
+/// 取得 OpenAPI 3 文件
+/// GET /api/openapi.json
+#[get("/api/openapi.json")]
+pub async fn openapi_json(spec: web::Data<utoipa::openapi::OpenApi>) -> impl Responder {
+    HttpResponse::Ok().json(spec.get_ref())
+}
+
+/// API 文件頁面
+/// GET /api/docs
+#[get("/api/docs")]
+pub async fn docs() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(DOCS_HTML)
+}
@@ -0,0 +1,333 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+use ipnet::IpNet;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::api::config::{ApiConfig, Quota};
+
+// 不限制的路徑：健康檢查與監控需要在流量尖峰時仍能回應
+const EXEMPT_PATHS: &[&str] = &["/", "/healthz", "/readyz", "/metrics"];
+
+// bucket 數量超過此值時，清除已補滿（閒置）的 bucket
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Budget {
+    Json,
+    Asset,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// 以 IP 為單位的 token bucket 流量限制，所有 worker 共用
+pub struct RateLimiter {
+    json: Quota,
+    asset: Quota,
+    trusted_proxies: Vec<IpNet>,
+    proxy_header: header::HeaderName,
+    allowlist: Vec<IpNet>,
+    buckets: Mutex<HashMap<(IpAddr, Budget), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &ApiConfig) -> Self {
+        RateLimiter {
+            json: config.json_rate_limit,
+            asset: config.asset_rate_limit,
+            trusted_proxies: config.trusted_proxies.clone(),
+            proxy_header: header::HeaderName::try_from(config.proxy_header.as_str())
+                .unwrap_or(header::X_FORWARDED_FOR),
+            allowlist: config.rate_limit_allowlist.clone(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn quota(&self, budget: Budget) -> Quota {
+        match budget {
+            Budget::Json => self.json,
+            Budget::Asset => self.asset,
+        }
+    }
+
+    /// 取得用戶端 IP：直接連線的位址屬於可信任的 proxy 時，
+    /// 從 proxy 標頭由右往左找出第一個不屬於 proxy 的位址
+    fn client_ip(&self, req: &ServiceRequest) -> Option<IpAddr> {
+        let peer = req.peer_addr()?.ip();
+        if !self.is_trusted_proxy(peer) {
+            return Some(peer);
+        }
+
+        let forwarded = req
+            .headers()
+            .get(&self.proxy_header)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        let mut client = peer;
+        for item in forwarded.rsplit(',') {
+            match item.trim().parse::<IpAddr>() {
+                Ok(ip) if self.is_trusted_proxy(ip) => client = ip,
+                Ok(ip) => return Some(ip),
+                Err(_) => break,
+            }
+        }
+        Some(client)
+    }
+
+    fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|network| network.contains(&ip))
+    }
+
+    fn is_allowed(&self, ip: IpAddr) -> bool {
+        self.allowlist.iter().any(|network| network.contains(&ip))
+    }
+
+    /// 嘗試消耗一個 token，額度不足時回傳需要等待的時間
+    fn acquire(&self, ip: IpAddr, budget: Budget) -> Result<(), Duration> {
+        self.acquire_at(ip, budget, Instant::now())
+    }
+
+    fn acquire_at(&self, ip: IpAddr, budget: Budget, now: Instant) -> Result<(), Duration> {
+        let quota = self.quota(budget);
+        if quota.per_minute == 0 {
+            return Ok(());
+        }
+        let rate = f64::from(quota.per_minute) / 60.0;
+        let capacity = f64::from(quota.burst.max(1));
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > PRUNE_THRESHOLD {
+            buckets.retain(|(_, budget), bucket| {
+                let quota = self.quota(*budget);
+                let refill = now.duration_since(bucket.updated_at).as_secs_f64() * f64::from(quota.per_minute) / 60.0;
+                bucket.tokens + refill < f64::from(quota.burst.max(1))
+            });
+        }
+
+        let bucket = buckets.entry((ip, budget)).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+fn budget_for(req: &ServiceRequest) -> Option<Budget> {
+    let path = req.path();
+    if EXEMPT_PATHS.contains(&path) {
+        None
+    } else if path.starts_with("/api/assets/") || path.starts_with("/static/") {
+        Some(Budget::Asset)
+    } else {
+        Some(Budget::Json)
+    }
+}
+
+/// 超過額度時回傳 429 與 Retry-After（秒）
+pub async fn limit_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let limiter = req.app_data::<web::Data<RateLimiter>>().cloned();
+    let (Some(limiter), Some(budget)) = (limiter, budget_for(&req)) else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+    let Some(ip) = limiter.client_ip(&req).filter(|ip| !limiter.is_allowed(*ip)) else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+
+    match limiter.acquire(ip, budget) {
+        Ok(()) => Ok(next.call(req).await?.map_into_left_body()),
+        Err(wait) => {
+            debug!(%ip, ?budget, "rate limit exceeded");
+            let retry_after = retry_after_secs(wait);
+            let response = HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+                .json(serde_json::json!({ "error": "rate limit exceeded", "retry_after": retry_after }));
+            Ok(req.into_response(response).map_into_right_body())
+        }
+    }
+}
+
+/// Retry-After 的秒數：無條件進位，至少 1 秒
+fn retry_after_secs(wait: Duration) -> u64 {
+    wait.as_secs_f64().ceil().max(1.0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::middleware::from_fn;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::App;
+
+    fn config(json: Quota, trusted_proxies: &[&str], allowlist: &[&str]) -> ApiConfig {
+        let networks = |items: &[&str]| items.iter().map(|item| item.parse().unwrap()).collect();
+        ApiConfig {
+            post_max_age: 60,
+            response_cache_capacity: 0,
+            response_cache_ttl: 0,
+            public_base_url: None,
+            shutdown_timeout: 1,
+            shutdown_drain_delay: 0,
+            json_rate_limit: json,
+            asset_rate_limit: Quota { per_minute: 0, burst: 0 },
+            trusted_proxies: networks(trusted_proxies),
+            proxy_header: "X-Forwarded-For".to_string(),
+            rate_limit_allowlist: networks(allowlist),
+            serve_static: false,
+            admin_token: None,
+            highlight_theme: "InspiredGitHub".to_string(),
+        }
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn request(peer: &str, forwarded: Option<&str>) -> ServiceRequest {
+        let mut req = TestRequest::get()
+            .uri("/api/posts")
+            .peer_addr(format!("{}:40000", peer).parse().unwrap());
+        if let Some(forwarded) = forwarded {
+            req = req.insert_header(("X-Forwarded-For", forwarded));
+        }
+        req.to_srv_request()
+    }
+
+    const UNLIMITED: Quota = Quota { per_minute: 0, burst: 0 };
+
+    #[test]
+    fn ignores_proxy_header_from_untrusted_peer() {
+        let limiter = RateLimiter::new(&config(UNLIMITED, &["10.0.0.0/8"], &[]));
+        let req = request("203.0.113.7", Some("198.51.100.1"));
+        assert_eq!(limiter.client_ip(&req), Some(ip("203.0.113.7")));
+    }
+
+    #[test]
+    fn reads_client_from_trusted_proxy() {
+        let limiter = RateLimiter::new(&config(UNLIMITED, &["10.0.0.0/8"], &[]));
+        // 最左側的位址可由用戶端偽造，取最右側不屬於 proxy 的位址
+        let req = request("10.0.0.2", Some("1.2.3.4, 198.51.100.1, 10.0.0.3"));
+        assert_eq!(limiter.client_ip(&req), Some(ip("198.51.100.1")));
+
+        let req = request("10.0.0.2", None);
+        assert_eq!(limiter.client_ip(&req), Some(ip("10.0.0.2")));
+
+        // 無法解析的項目之後的位址不採用
+        let req = request("10.0.0.2", Some("198.51.100.1, bogus, 10.0.0.3"));
+        assert_eq!(limiter.client_ip(&req), Some(ip("10.0.0.3")));
+    }
+
+    #[test]
+    fn refills_tokens_over_time() {
+        let limiter = RateLimiter::new(&config(Quota { per_minute: 60, burst: 2 }, &[], &[]));
+        let client = ip("203.0.113.7");
+        let start = Instant::now();
+        assert!(limiter.acquire_at(client, Budget::Json, start).is_ok());
+        assert!(limiter.acquire_at(client, Budget::Json, start).is_ok());
+        let wait = limiter.acquire_at(client, Budget::Json, start).unwrap_err();
+        assert!((wait.as_secs_f64() - 1.0).abs() < 0.01, "{:?}", wait);
+
+        let wait = limiter.acquire_at(client, Budget::Json, start + Duration::from_millis(500)).unwrap_err();
+        assert!((wait.as_secs_f64() - 0.5).abs() < 0.01, "{:?}", wait);
+        assert!(limiter.acquire_at(client, Budget::Json, start + Duration::from_secs(1)).is_ok());
+
+        // 補充不超過 burst
+        let later = start + Duration::from_secs(60);
+        assert!(limiter.acquire_at(client, Budget::Json, later).is_ok());
+        assert!(limiter.acquire_at(client, Budget::Json, later).is_ok());
+        assert!(limiter.acquire_at(client, Budget::Json, later).is_err());
+
+        // 其他 IP 與其他額度各自計算
+        assert!(limiter.acquire_at(ip("203.0.113.8"), Budget::Json, later).is_ok());
+        assert!(limiter.acquire_at(client, Budget::Asset, later).is_ok());
+    }
+
+    #[test]
+    fn rounds_retry_after_up() {
+        assert_eq!(retry_after_secs(Duration::from_millis(10)), 1);
+        assert_eq!(retry_after_secs(Duration::from_millis(1000)), 1);
+        assert_eq!(retry_after_secs(Duration::from_millis(1200)), 2);
+        assert_eq!(retry_after_secs(Duration::from_secs(60)), 60);
+    }
+
+    // 依序送出 `count` 個請求，回傳各自的狀態碼與 Retry-After
+    async fn statuses(
+        config: ApiConfig,
+        peer: &str,
+        forwarded: Option<&str>,
+        path: &str,
+        count: usize,
+    ) -> Vec<(StatusCode, Option<String>)> {
+        let limiter = web::Data::new(RateLimiter::new(&config));
+        let app = init_service(
+            App::new()
+                .app_data(limiter)
+                .wrap(from_fn(limit_requests))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+        let mut statuses = Vec::new();
+        for _ in 0..count {
+            let mut req = TestRequest::get()
+                .uri(path)
+                .peer_addr(format!("{}:40000", peer).parse().unwrap());
+            if let Some(forwarded) = forwarded {
+                req = req.insert_header(("X-Forwarded-For", forwarded));
+            }
+            let resp = call_service(&app, req.to_request()).await;
+            let retry_after = resp
+                .headers()
+                .get(header::RETRY_AFTER)
+                .map(|value| value.to_str().unwrap().to_string());
+            statuses.push((resp.status(), retry_after));
+        }
+        statuses
+    }
+
+    const ONE_PER_MINUTE: Quota = Quota { per_minute: 1, burst: 1 };
+
+    #[actix_web::test]
+    async fn responds_429_with_retry_after() {
+        let statuses = statuses(config(ONE_PER_MINUTE, &[], &[]), "203.0.113.7", None, "/api/posts", 2).await;
+        assert_eq!(statuses[0], (StatusCode::OK, None));
+        assert_eq!(statuses[1], (StatusCode::TOO_MANY_REQUESTS, Some("60".to_string())));
+    }
+
+    #[actix_web::test]
+    async fn allowlisted_and_exempt_requests_bypass_limit() {
+        let allowlisted = statuses(config(ONE_PER_MINUTE, &[], &["203.0.113.0/24"]), "203.0.113.7", None, "/api/posts", 3).await;
+        assert!(allowlisted.iter().all(|(status, _)| *status == StatusCode::OK));
+
+        let exempt = statuses(config(ONE_PER_MINUTE, &[], &[]), "203.0.113.7", None, "/healthz", 3).await;
+        assert!(exempt.iter().all(|(status, _)| *status == StatusCode::OK));
+    }
+
+    #[actix_web::test]
+    async fn spoofed_header_does_not_bypass_allowlist() {
+        // 不可信任的來源帶入 allowlist 中的位址，仍以連線位址計算
+        let config = config(ONE_PER_MINUTE, &[], &["127.0.0.1/32"]);
+        let statuses = statuses(config, "203.0.113.7", Some("127.0.0.1"), "/api/posts", 2).await;
+        assert_eq!(statuses[1].0, StatusCode::TOO_MANY_REQUESTS);
+    }
+}
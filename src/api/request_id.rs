@@ -0,0 +1,58 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::Error;
+use std::time::Instant;
+use tracing::{field, info, info_span, Instrument};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+// 沿用客戶端 / proxy 傳入的 request ID 前的檢查，避免任意內容寫入日誌
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 128
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// 為每個請求建立 tracing span 並寫入 access log
+/// 沿用請求帶入的 X-Request-Id，沒有時產生新的 UUID，並回傳在回應標頭中
+pub async fn trace_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let request_id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let span = info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.path(),
+        route = field::Empty,
+        status = field::Empty,
+    );
+    if let Some(route) = req.match_pattern() {
+        span.record("route", route.as_str());
+    }
+
+    let started = Instant::now();
+    let mut response = next.call(req).instrument(span.clone()).await?;
+
+    let status = response.status();
+    span.record("status", status.as_u16());
+    span.in_scope(|| {
+        info!(latency_ms = started.elapsed().as_secs_f64() * 1000.0, "request completed");
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    Ok(response)
+}
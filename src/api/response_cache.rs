@@ -0,0 +1,165 @@
+use futures_util::{stream, StreamExt};
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_postgres::{AsyncMessage, NoTls};
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+use crate::api::http_cache::CacheableJson;
+use crate::api::metrics::metrics;
+use crate::common::db::POSTS_CHANGED_CHANNEL;
+
+// LISTEN 連線中斷後重新連線前的等待時間
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// 文章與列表查詢結果的記憶體快取（LRU + TTL）
+/// 由 CLI 寫入時發出的 NOTIFY 觸發失效，讓多個 API 實例保持一致
+pub struct ResponseCache {
+    entries: Option<Mutex<LruCache<String, (Instant, CacheableJson)>>>,
+    ttl: Duration,
+    // 每次失效時遞增；查詢期間發生失效的結果不寫入快取，避免放回過期的資料
+    generation: AtomicU64,
+}
+
+impl ResponseCache {
+    /// capacity 為 0 時停用快取
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        ResponseCache {
+            entries: NonZeroUsize::new(capacity).map(|capacity| Mutex::new(LruCache::new(capacity))),
+            ttl,
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    // 回應中的資源 URL 以 `base_url` 組成，未設定 API_BASE_URL 時依請求的 Host 而不同，因此也是 key 的一部分
+
+    /// `fieldset` 為 `PostFieldset::cache_key`，不同欄位組合分別快取
+    pub fn list_key(page: u64, limit: u64, fieldset: &str, base_url: &str) -> String {
+        format!("posts:{}:{}:{}|{}", page, limit, fieldset, base_url)
+    }
+
+    pub fn post_key(uuid: &Uuid, fieldset: &str, base_url: &str) -> String {
+        format!("post:{}:{}|{}", uuid, fieldset, base_url)
+    }
+
+    pub fn post_assets_key(uuid: &Uuid, base_url: &str) -> String {
+        format!("post_assets:{}:{}", uuid, base_url)
+    }
+
+    pub fn post_references_key(uuid: &Uuid, base_url: &str) -> String {
+        format!("post_references:{}:{}", uuid, base_url)
+    }
+
+    pub fn get(&self, key: &str) -> Option<CacheableJson> {
+        let mut entries = self.entries.as_ref()?.lock().unwrap();
+        let found = match entries.get(key) {
+            Some((inserted_at, json)) if inserted_at.elapsed() < self.ttl => Some(json.clone()),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        };
+        metrics().record_cache_lookup(found.is_some());
+        found
+    }
+
+    /// 目前的世代，需在查詢資料庫之前取得並傳給 `insert`
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// `generation` 之後若曾經失效（`invalidate` 或 `clear`），結果可能已過期，不寫入快取
+    pub fn insert(&self, key: String, json: CacheableJson, generation: u64) {
+        if let Some(entries) = &self.entries {
+            let mut entries = entries.lock().unwrap();
+            if self.generation() == generation {
+                entries.put(key, (Instant::now(), json));
+            }
+        }
+    }
+
+    /// 依通知內容使快取失效：payload 為文章 UUID 時只清除該文章（所有欄位組合與位址）與所有列表，否則全部清除
+    pub fn invalidate(&self, payload: &str) {
+        let Some(entries) = &self.entries else { return };
+        let mut entries = entries.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+
+        let Ok(uuid) = Uuid::parse_str(payload.trim()) else {
+            entries.clear();
+            return;
+        };
+
+        let prefixes = [
+            "posts:".to_string(),
+            format!("post:{}:", uuid),
+            format!("post_assets:{}:", uuid),
+            format!("post_references:{}:", uuid),
+        ];
+        let stale: Vec<String> = entries
+            .iter()
+            .map(|(key, _)| key.clone())
+            .filter(|key| prefixes.iter().any(|prefix| key.starts_with(prefix)))
+            .collect();
+        for key in stale {
+            entries.pop(&key);
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Some(entries) = &self.entries {
+            let mut entries = entries.lock().unwrap();
+            self.generation.fetch_add(1, Ordering::AcqRel);
+            entries.clear();
+        }
+    }
+}
+
+/// 以獨立連線 LISTEN 文章異動通知，連線中斷時自動重連
+pub async fn listen_for_invalidations(cache: actix_web::web::Data<ResponseCache>, database_url: String) {
+    loop {
+        if let Err(e) = listen_once(&cache, &database_url).await {
+            error!(error = %e, "cache invalidation listener failed");
+        }
+        // 重新連線前可能漏掉通知，保守起見清空快取
+        cache.clear();
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn listen_once(cache: &ResponseCache, database_url: &str) -> Result<(), tokio_postgres::Error> {
+    let (client, mut connection) = tokio_postgres::connect(database_url, NoTls).await?;
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+    actix_web::rt::spawn(async move {
+        while let Some(message) = messages.next().await {
+            match message {
+                Ok(AsyncMessage::Notification(notification)) => {
+                    if tx.send(notification.payload().to_string()).is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(error = %e, "cache invalidation connection closed");
+                    break;
+                }
+            }
+        }
+    });
+
+    client.batch_execute(&format!("LISTEN {}", POSTS_CHANGED_CHANNEL)).await?;
+    cache.clear();
+
+    // 連線結束時 sender 會被釋放，recv 回傳 None
+    while let Some(payload) = rx.recv().await {
+        debug!(payload = %payload, "invalidating response cache");
+        cache.invalidate(&payload);
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use actix_web::dev::ServerHandle;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::info;
+
+/// 伺服器是否正在關閉；關閉開始後 /readyz 回傳失敗，讓 load balancer 先把流量移走
+#[derive(Debug, Default)]
+pub struct ShutdownState {
+    draining: AtomicBool,
+}
+
+impl ShutdownState {
+    pub fn begin_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+}
+
+/// 等待 SIGTERM 或 Ctrl-C
+pub async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = sigterm.recv() => {}
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// 收到訊號後的關閉流程：
+/// 1. readiness 轉為失敗，等待 `drain_delay` 讓 load balancer 停止送入新流量（期間再收到訊號則立即進入下一步）
+/// 2. 停止接受新連線，等待進行中的請求完成（上限為 HttpServer 的 shutdown_timeout）
+pub async fn graceful_shutdown(handle: ServerHandle, state: &ShutdownState, drain_delay: Duration) {
+    wait_for_signal().await;
+    state.begin_draining();
+    info!(drain_delay_secs = drain_delay.as_secs(), "shutdown signal received; draining");
+
+    tokio::select! {
+        _ = tokio::time::sleep(drain_delay) => {}
+        _ = wait_for_signal() => info!("second shutdown signal received; skipping drain delay"),
+    }
+
+    info!("stopping server; waiting for in-flight requests");
+    handle.stop(true).await;
+}
@@ -1,9 +1,15 @@
-use actix_files::Files;
-use actix_web::{get, web, App, HttpResponse, HttpServer, Responder, http::header};
-use actix_cors::Cors;
+use actix_web::{get, web, HttpResponse, HttpServer, Responder};
 use dotenvy::dotenv;
-use journal_core::common::db;
-use journal_core::api::handlers::{post_handler, asset_handler};
+use journal_core::api::{self, shutdown};
+use journal_core::api::handlers::asset_handler;
+use journal_core::api::rate_limit::RateLimiter;
+use journal_core::api::shutdown::ShutdownState;
+use journal_core::common::{db, telemetry};
+use journal_core::api::config::ApiConfig;
+use journal_core::api::response_cache::{self, ResponseCache};
+use std::time::Duration;
+use tracing::info;
+use tracing::level_filters::LevelFilter;
 
 #[get("/")]
 async fn health_check() -> impl Responder {
@@ -13,46 +19,76 @@ async fn health_check() -> impl Responder {
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
+    telemetry::init(LevelFilter::INFO);
     
     let pool = db::create_pool();
+    let config = ApiConfig::from_env();
+
+    // 回應快取在所有 worker 間共用，由 LISTEN/NOTIFY 觸發失效
+    let cache = web::Data::new(ResponseCache::new(
+        config.response_cache_capacity,
+        Duration::from_secs(config.response_cache_ttl),
+    ));
+    if config.response_cache_capacity > 0 {
+        actix_web::rt::spawn(response_cache::listen_for_invalidations(cache.clone(), db::database_url()));
+    }
+
+    if config.public_base_url.is_none() {
+        info!("API_BASE_URL is not set; asset URLs use each request's scheme and Host");
+    }
 
     println!("🚀 Server started successfully");
-    println!("📍 Health check: http://localhost:8080/");
+    println!("📍 Health check: http://localhost:8080/ (liveness: /healthz, readiness: /readyz)");
     println!("📚 API endpoints (Read-Only):");
     println!("   GET    /api/posts           - 取得文章列表");
     println!("   GET    /api/posts/:uuid     - 取得單一文章");
     println!("   GET    /api/assets/:uuid    - 取得資源檔案");
     println!("   GET    /api/posts/:uuid/assets - 取得文章的所有資源");
+    if config.serve_static {
+        println!("   GET    /static/*            - 靜態檔案（SERVE_STATIC）");
+    }
+    println!("📈 Metrics: http://localhost:8080/metrics");
+    println!("📖 API 文件: http://localhost:8080/api/docs (OpenAPI: /api/openapi.json)");
     println!();
     println!("💡 使用 CLI 進行文章管理：");
     println!("   cargo run --bin cli -- add -t 'Title' -f post.md");
 
-    HttpServer::new(move || {
-        // CORS 設定
-        let cors = Cors::default()
-            .allowed_origin("http://localhost:3000")     // 允許前端的 origin
-            .allowed_origin("http://localhost:5173")     // Vite 預設 port
-            .allowed_origin("http://127.0.0.1:3000")
-            .allowed_origin("http://127.0.0.1:5173")
-            .allowed_methods(vec!["GET"])                // 只允許 GET
-            .allowed_headers(vec![
-                header::AUTHORIZATION,
-                header::ACCEPT,
-                header::CONTENT_TYPE,
-            ])
-            .max_age(3600);                              // preflight 快取 1 小時
-
-        App::new()
-            .wrap(cors)                                   // 加入 CORS middleware
-            .app_data(web::Data::new(pool.clone()))
+    let serve_static = config.serve_static;
+    let shutdown_timeout = config.shutdown_timeout;
+    let drain_delay = Duration::from_secs(config.shutdown_drain_delay);
+    let shutdown_state = web::Data::new(ShutdownState::default());
+    let rate_limiter = web::Data::new(RateLimiter::new(&config));
+    let app_pool = pool.clone();
+    let app_shutdown_state = shutdown_state.clone();
+    let server = HttpServer::new(move || {
+        api::app_with_middleware()
+            .app_data(web::Data::new(app_pool.clone()))
+            .app_data(web::Data::new(config.clone()))
+            .app_data(cache.clone())
+            .app_data(app_shutdown_state.clone())
+            .app_data(rate_limiter.clone())
             .service(health_check)
-            .service(post_handler::get_posts)
-            .service(post_handler::get_post_by_uuid)
-            .service(asset_handler::get_asset)
-            .service(asset_handler::get_post_assets)
-            .service(Files::new("/static", "static").show_files_listing())
+            .configure(|cfg| {
+                // uploads 只提供仍被文章引用的檔案，其他靜態檔案不提供目錄列表
+                if serve_static {
+                    cfg.service(asset_handler::get_static_upload)
+                        .service(asset_handler::static_files());
+                }
+            })
     })
+    // 訊號由 shutdown::graceful_shutdown 處理，先讓 readiness 失敗再停止接受連線
+    .disable_signals()
+    .shutdown_timeout(shutdown_timeout)
     .bind("0.0.0.0:8080")?
-    .run()
-    .await
+    .run();
+
+    let handle = server.handle();
+    actix_web::rt::spawn(async move {
+        shutdown::graceful_shutdown(handle, &shutdown_state, drain_delay).await;
+    });
+
+    server.await?;
+    pool.close();
+    info!("server stopped");
+    Ok(())
 }
\ No newline at end of file
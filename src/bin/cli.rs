@@ -1,16 +1,35 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, Parser, Subcommand};
 use dotenvy::dotenv;
 use std::error::Error;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::level_filters::LevelFilter;
 use uuid::Uuid;
 
-use journal_core::common::db;
+use journal_core::common::{db, highlight, telemetry};
+use journal_core::common::models::DEFAULT_API_BASE_URL;
+use journal_core::cli::archive::{self, ConflictStrategy};
 use journal_core::cli::commands;
+use journal_core::cli::link_checker::{self, LinkCheckOptions};
+use journal_core::cli::output::{self, Message, OutputFormat};
+use journal_core::cli::site::{self, BuildSiteOptions};
+use journal_core::cli::sync::{self, SyncOptions};
+use journal_core::cli::watch;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Output format
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+    /// Print only essential output (e.g. just the UUID for `add`) and log errors only
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Log more detail (-v info, -vv debug, -vvv trace); RUST_LOG overrides this
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
 }
 
 #[derive(Subcommand, Debug)]
@@ -60,6 +79,72 @@ enum Commands {
         #[arg(short, long)]
         uuid: String,
     },
+    /// Export all posts and assets to a portable archive
+    Export {
+        #[arg(short, long, default_value = "journal.tar.zst")]
+        out: String,
+    },
+    /// Restore posts and assets from an exported archive
+    Restore {
+        #[arg(short, long)]
+        file: String,
+        /// How to handle posts or assets whose UUID already exists
+        #[arg(long, value_enum, default_value_t = ConflictStrategy::Skip)]
+        on_conflict: ConflictStrategy,
+    },
+    /// Render all posts into a static website
+    BuildSite {
+        #[arg(short, long, default_value = "public")]
+        out: PathBuf,
+        /// Public URL of the site, used for feeds and the sitemap (defaults to SITE_BASE_URL)
+        #[arg(long)]
+        base_url: Option<String>,
+        /// Directory with layout.html / post.html / list.html overriding the built-in templates
+        #[arg(long)]
+        templates: Option<PathBuf>,
+        #[arg(long, default_value_t = 10)]
+        per_page: usize,
+        #[arg(long, default_value = "Journal")]
+        site_title: String,
+        /// Color theme for highlighted code, written to highlight.css (defaults to HIGHLIGHT_THEME or InspiredGitHub)
+        #[arg(long)]
+        highlight_theme: Option<String>,
+    },
+    /// Mirror a folder of markdown files into the database
+    Sync {
+        dir: PathBuf,
+        /// Delete posts whose source file was removed from the folder
+        #[arg(long)]
+        delete: bool,
+        /// Print the plan without applying it
+        #[arg(long)]
+        dry_run: bool,
+        /// Keep the file-to-UUID mapping in a lockfile instead of writing front matter
+        #[arg(long)]
+        lockfile: bool,
+    },
+    /// Check external links in posts and record broken ones
+    CheckLinks {
+        /// Post to check (repeatable)
+        #[arg(short, long, required_unless_present = "all")]
+        uuid: Vec<String>,
+        /// Check every post
+        #[arg(long, conflicts_with = "uuid")]
+        all: bool,
+        /// Number of hosts checked in parallel
+        #[arg(long, default_value_t = 8)]
+        concurrency: usize,
+        /// Delay between requests to the same host, in milliseconds
+        #[arg(long, default_value_t = 1000)]
+        delay_ms: u64,
+    },
+    /// Re-process a markdown file or directory on change and serve a live preview
+    Watch {
+        #[arg(short, long)]
+        file: PathBuf,
+        #[arg(short, long, default_value_t = 4000)]
+        port: u16,
+    },
 }
 
 #[tokio::main]
@@ -70,42 +155,120 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     let api_base_url = std::env::var("API_BASE_URL").ok();
     
     let cli = Cli::parse();
+    telemetry::init(match (cli.quiet, cli.verbose) {
+        (true, _) => LevelFilter::ERROR,
+        (false, 0) => LevelFilter::WARN,
+        (false, 1) => LevelFilter::INFO,
+        (false, 2) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    });
     let pool = db::create_pool();
 
+    let format = cli.output;
+
     match &cli.command {
         Commands::Add { title, file } => {
             let uuid = commands::add_post(&pool, title, file, api_base_url.as_deref()).await?;
-            println!("Blog post '{}' added successfully with UUID: {}", title, uuid);
+            if cli.quiet {
+                println!("{}", uuid);
+            } else {
+                let message = format!("Blog post '{}' added successfully with UUID: {}", title, uuid);
+                output::print(&Message::new("added", Some(uuid), message), format)?;
+            }
         }
         Commands::List { page, limit } => {
-            commands::list_posts(&pool, *page, *limit).await?;
+            let posts = commands::list_posts(&pool, *page, *limit).await?;
+            output::print(&posts, format)?;
         }
         Commands::Get { uuid } => {
             let post_uuid = Uuid::parse_str(uuid)?;
-            commands::get_post(&pool, post_uuid).await?;
+            let post = commands::get_post(&pool, post_uuid).await?;
+            output::print(&post, format)?;
         }
         Commands::Update { uuid, title, file } => {
             let post_uuid = Uuid::parse_str(uuid)?;
-            commands::update_post(&pool, post_uuid, title.clone(), file.clone(), api_base_url.as_deref()).await?;
-            println!("Blog post {} updated successfully.", uuid);
+            let updated = commands::update_post(&pool, post_uuid, title.clone(), file.clone(), api_base_url.as_deref()).await?;
+            let message = if updated {
+                Message::new("updated", Some(post_uuid), format!("Blog post {} updated successfully.", uuid))
+            } else {
+                Message::new("unchanged", Some(post_uuid), format!("No updates provided for post UUID {}.", uuid))
+            };
+            print_message(&message, format, cli.quiet)?;
         }
         Commands::Delete { uuid } => {
             let post_uuid = Uuid::parse_str(uuid)?;
             commands::delete_post(&pool, post_uuid).await?;
-            println!("Blog post {} deleted successfully.", uuid);
+            let message = Message::new("deleted", Some(post_uuid), format!("Blog post {} deleted successfully.", uuid));
+            print_message(&message, format, cli.quiet)?;
         }
         Commands::InitDb => {
             db::init_db(&pool).await;
-            println!("Database initialized successfully.");
+            print_message(&Message::new("initialized", None, "Database initialized successfully."), format, cli.quiet)?;
         }
         Commands::TestMarkdown { file } => {
-            commands::test_markdown(file, api_base_url.as_deref()).await?;
+            let result = commands::test_markdown(file, api_base_url.as_deref()).await?;
+            output::print(&result, format)?;
         }
         Commands::ListAssets { uuid } => {
             let post_uuid = Uuid::parse_str(uuid)?;
-            commands::list_assets(&pool, post_uuid).await?;
+            let base_url = api_base_url.as_deref().unwrap_or(DEFAULT_API_BASE_URL);
+            let assets = commands::list_assets(&pool, post_uuid, base_url).await?;
+            output::print(&assets, format)?;
+        }
+        Commands::Export { out } => {
+            let summary = archive::export(&pool, out).await?;
+            output::print(&summary, format)?;
+        }
+        Commands::Restore { file, on_conflict } => {
+            let summary = archive::restore(&pool, file, *on_conflict, api_base_url.as_deref()).await?;
+            output::print(&summary, format)?;
+        }
+        Commands::BuildSite { out, base_url, templates, per_page, site_title, highlight_theme } => {
+            let options = BuildSiteOptions {
+                out: out.clone(),
+                base_url: base_url.clone().or_else(|| std::env::var("SITE_BASE_URL").ok()),
+                templates: templates.clone(),
+                per_page: *per_page,
+                site_title: site_title.clone(),
+                highlight_theme: highlight_theme
+                    .clone()
+                    .or_else(|| std::env::var("HIGHLIGHT_THEME").ok())
+                    .unwrap_or_else(|| highlight::DEFAULT_THEME.to_string()),
+            };
+            let summary = site::build_site(&pool, &options).await?;
+            output::print(&summary, format)?;
+        }
+        Commands::Sync { dir, delete, dry_run, lockfile } => {
+            let options = SyncOptions {
+                dir: dir.clone(),
+                delete: *delete,
+                dry_run: *dry_run,
+                lockfile: *lockfile,
+            };
+            let report = sync::sync(&pool, &options, api_base_url.as_deref()).await?;
+            output::print(&report, format)?;
+        }
+        Commands::CheckLinks { uuid, all: _, concurrency, delay_ms } => {
+            let options = LinkCheckOptions {
+                uuids: uuid.iter().map(|uuid| Uuid::parse_str(uuid)).collect::<Result<_, _>>()?,
+                concurrency: *concurrency,
+                host_delay: Duration::from_millis(*delay_ms),
+            };
+            let report = link_checker::check_links(&pool, &options).await?;
+            output::print(&report, format)?;
+        }
+        Commands::Watch { file, port } => {
+            watch::watch(file, *port).await?;
         }
     }
 
     Ok(())
+}
+
+/// 狀態訊息在 --quiet 且為表格輸出時省略
+fn print_message(message: &Message, format: OutputFormat, quiet: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if quiet && format == OutputFormat::Table {
+        return Ok(());
+    }
+    output::print(message, format)
 }
\ No newline at end of file
@@ -0,0 +1,479 @@
+use clap::ValueEnum;
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+use time::OffsetDateTime;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::cli::front_matter::{self, FrontMatter};
+use crate::cli::markdown_processor::{self, UPLOADS_DIR};
+use crate::cli::svg_sanitizer;
+use crate::common::db;
+use crate::common::models::{asset_url, AssetKind, Post, PostAsset};
+use crate::common::telemetry::traced_query;
+
+const MANIFEST_PATH: &str = "manifest.json";
+const POSTS_DIR: &str = "posts";
+const ASSETS_DIR: &str = "assets";
+const FORMAT_VERSION: u32 = 1;
+
+/// 還原時遇到相同 UUID 的處理方式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// 遇到衝突時中止還原（不寫入任何資料）
+    Fail,
+    /// 保留資料庫中既有的資料
+    Skip,
+    /// 以封存檔的內容取代既有資料
+    Overwrite,
+}
+
+/// 封存檔中的 manifest.json
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    pub format_version: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+    pub posts: Vec<ManifestPost>,
+    pub assets: Vec<ManifestAsset>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ManifestPost {
+    pub uuid: Uuid,
+    pub title: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// 舊版封存檔沒有此欄位，還原時以 created_at 代替
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub updated_at: Option<OffsetDateTime>,
+    /// 封存檔內的 markdown 路徑
+    pub path: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ManifestAsset {
+    pub asset_uuid: Uuid,
+    pub post_uuid: Uuid,
+    pub original_url: String,
+    /// 相對於 uploads 目錄的路徑
+    pub file_path: String,
+    pub content_type: Option<String>,
+    pub file_size: Option<i64>,
+    /// 舊版封存檔沒有此欄位，視為 inline
+    #[serde(default)]
+    pub kind: AssetKind,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ExportSummary {
+    pub posts: usize,
+    pub assets: usize,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct RestoreSummary {
+    pub posts_restored: usize,
+    pub posts_skipped: usize,
+    pub assets_restored: usize,
+    pub assets_skipped: usize,
+}
+
+/// 匯出所有文章與資源到 tar.zst 封存檔
+/// 文章中的 `/api/assets/{uuid}` 連結會改寫為封存檔內的相對路徑
+pub async fn export(pool: &Pool, out: &str) -> Result<ExportSummary, Box<dyn Error + Send + Sync>> {
+    let client = pool.get().await?;
+
+    let posts: Vec<Post> = traced_query(
+        "export_posts",
+        client.query("SELECT id, uuid, title, content, tags, toc, summary, excerpt, word_count, reading_minutes, created_at, updated_at FROM posts ORDER BY created_at", &[]),
+    )
+    .await?
+    .into_iter()
+    .map(Post::from)
+    .collect();
+
+    let asset_rows = traced_query(
+        "export_assets",
+        client.query(
+            "SELECT a.*, p.uuid AS post_uuid FROM post_assets a
+             JOIN posts p ON p.id = a.post_id
+             ORDER BY a.created_at",
+            &[],
+        ),
+    )
+    .await?;
+
+    let mut assets: Vec<(Uuid, PostAsset)> = Vec::new();
+    for row in asset_rows {
+        let post_uuid: Uuid = row.get("post_uuid");
+        let asset = PostAsset::from(row);
+        if PathBuf::from(UPLOADS_DIR).join(&asset.file_path).is_file() {
+            assets.push((post_uuid, asset));
+        } else {
+            warn!(asset_uuid = %asset.asset_uuid, file_path = %asset.file_path, "skipping asset: file not found on disk");
+        }
+    }
+
+    let asset_paths: HashMap<Uuid, String> = assets
+        .iter()
+        .map(|(_, asset)| (asset.asset_uuid, asset.file_path.clone()))
+        .collect();
+
+    let mut manifest = Manifest {
+        format_version: FORMAT_VERSION,
+        exported_at: OffsetDateTime::now_utc(),
+        posts: Vec::new(),
+        assets: Vec::new(),
+    };
+
+    let encoder = zstd::Encoder::new(File::create(out)?, 0)?.auto_finish();
+    let mut builder = tar::Builder::new(encoder);
+
+    let mut post_files = Vec::new();
+    for post in &posts {
+        let path = format!("{}/{}.md", POSTS_DIR, post.uuid);
+        let created_at = OffsetDateTime::from(post.created_at);
+
+        // 以 posts/ 為基準的相對路徑指向 assets/
+        let body = markdown_processor::rewrite_asset_links(&post.content, |uuid| {
+            asset_paths
+                .get(&uuid)
+                .map(|file_path| format!("../{}/{}", ASSETS_DIR, file_path))
+        });
+        let front_matter = FrontMatter {
+            uuid: Some(post.uuid),
+            title: Some(post.title.clone()),
+            created_at: Some(created_at),
+            tags: post.tags.clone(),
+            summary: post.summary.clone(),
+            no_download: Vec::new(),
+        };
+        post_files.push((path.clone(), front_matter::render(&front_matter, &body)?));
+
+        manifest.posts.push(ManifestPost {
+            uuid: post.uuid,
+            title: post.title.clone(),
+            tags: post.tags.clone(),
+            created_at,
+            updated_at: Some(OffsetDateTime::from(post.updated_at)),
+            path,
+        });
+    }
+
+    for (post_uuid, asset) in &assets {
+        manifest.assets.push(ManifestAsset {
+            asset_uuid: asset.asset_uuid,
+            post_uuid: *post_uuid,
+            original_url: asset.original_url.clone(),
+            file_path: asset.file_path.clone(),
+            content_type: asset.content_type.clone(),
+            file_size: asset.file_size,
+            kind: asset.kind,
+            created_at: OffsetDateTime::from(asset.created_at),
+        });
+    }
+
+    // manifest 放在最前面，方便還原時先讀取
+    append_bytes(&mut builder, MANIFEST_PATH, &serde_json::to_vec_pretty(&manifest)?)?;
+    for (path, content) in &post_files {
+        append_bytes(&mut builder, path, content.as_bytes())?;
+    }
+    for (_, asset) in &assets {
+        builder.append_path_with_name(
+            PathBuf::from(UPLOADS_DIR).join(&asset.file_path),
+            format!("{}/{}", ASSETS_DIR, asset.file_path),
+        )?;
+    }
+    builder.into_inner()?;
+
+    Ok(ExportSummary {
+        posts: manifest.posts.len(),
+        assets: manifest.assets.len(),
+    })
+}
+
+/// 從封存檔還原文章與資源，保留原本的 UUID 與時間戳記
+pub async fn restore(
+    pool: &Pool,
+    archive_path: &str,
+    on_conflict: ConflictStrategy,
+    api_base_url: Option<&str>,
+) -> Result<RestoreSummary, Box<dyn Error + Send + Sync>> {
+    let base_url = match api_base_url {
+        Some(url) => url.to_string(),
+        None => std::env::var("API_BASE_URL").unwrap_or_default(),
+    };
+
+    // 第一次讀取：manifest 與文章內容
+    let mut manifest: Option<Manifest> = None;
+    let mut post_files: HashMap<String, String> = HashMap::new();
+    {
+        let mut archive = open_archive(archive_path)?;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_string_lossy().into_owned();
+            if path == MANIFEST_PATH {
+                let mut buf = Vec::new();
+                entry.read_to_end(&mut buf)?;
+                manifest = Some(serde_json::from_slice(&buf)?);
+            } else if path.starts_with(POSTS_DIR) {
+                let mut content = String::new();
+                entry.read_to_string(&mut content)?;
+                post_files.insert(path, content);
+            }
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| invalid_archive("manifest.json is missing"))?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(invalid_archive(&format!(
+            "unsupported archive format version {}",
+            manifest.format_version
+        )));
+    }
+
+    // 資源路徑會寫入資料庫並在提供檔案時與 uploads 目錄組合，任何一個不安全就拒絕整個封存檔
+    if let Some(asset) = manifest.assets.iter().find(|asset| !is_safe_asset_path(&asset.file_path)) {
+        return Err(invalid_archive(&format!("unsafe asset path {}", asset.file_path)));
+    }
+
+    // 封存檔內的相對路徑改回 asset API 連結
+    let asset_links: HashMap<String, String> = manifest
+        .assets
+        .iter()
+        .map(|asset| {
+            (
+                format!("../{}/{}", ASSETS_DIR, asset.file_path),
+                asset_url(&base_url, asset.asset_uuid),
+            )
+        })
+        .collect();
+
+    let mut summary = RestoreSummary::default();
+    let mut restored_posts: HashMap<Uuid, i32> = HashMap::new();
+
+    let mut client = pool.get().await?;
+    let tx = client.transaction().await?;
+
+    for post in &manifest.posts {
+        let raw = post_files
+            .get(&post.path)
+            .ok_or_else(|| invalid_archive(&format!("{} is missing", post.path)))?;
+        let (front_matter, body) = front_matter::split(raw)?;
+
+        let mut content = body.to_string();
+        for (relative, url) in &asset_links {
+            content = content.replace(relative, url);
+        }
+
+        let exists = traced_query(
+            "find_post",
+            tx.query_opt("SELECT id FROM posts WHERE uuid = $1", &[&post.uuid]),
+        )
+        .await?
+        .is_some();
+        if exists {
+            match on_conflict {
+                ConflictStrategy::Fail => {
+                    return Err(conflict(&format!("post {} already exists", post.uuid)));
+                }
+                ConflictStrategy::Skip => {
+                    summary.posts_skipped += 1;
+                    continue;
+                }
+                ConflictStrategy::Overwrite => {
+                    traced_query(
+                        "delete_post",
+                        tx.execute("DELETE FROM posts WHERE uuid = $1", &[&post.uuid]),
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        let row = traced_query(
+            "restore_post",
+            tx.query_one(
+                "INSERT INTO posts (uuid, title, content, tags, created_at, updated_at)
+                 VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+                &[&post.uuid, &post.title, &content, &post.tags, &SystemTime::from(post.created_at),
+                  &SystemTime::from(post.updated_at.unwrap_or(post.created_at))],
+            ),
+        )
+        .await?;
+        let post_id: i32 = row.get("id");
+        let post_summary = front_matter.as_ref().and_then(|fm| fm.summary.as_deref());
+        db::update_derived_fields(&tx, post_id, &content, post_summary).await?;
+        restored_posts.insert(post.uuid, post_id);
+        summary.posts_restored += 1;
+    }
+
+    let mut restored_assets: HashMap<String, Uuid> = HashMap::new();
+    for asset in &manifest.assets {
+        let Some(post_id) = restored_posts.get(&asset.post_uuid) else {
+            summary.assets_skipped += 1;
+            continue;
+        };
+
+        let exists = traced_query(
+            "find_asset",
+            tx.query_opt("SELECT id FROM post_assets WHERE asset_uuid = $1", &[&asset.asset_uuid]),
+        )
+        .await?
+        .is_some();
+        if exists {
+            match on_conflict {
+                ConflictStrategy::Fail => {
+                    return Err(conflict(&format!("asset {} already exists", asset.asset_uuid)));
+                }
+                ConflictStrategy::Skip => {
+                    summary.assets_skipped += 1;
+                    continue;
+                }
+                ConflictStrategy::Overwrite => {
+                    traced_query(
+                        "delete_asset",
+                        tx.execute("DELETE FROM post_assets WHERE asset_uuid = $1", &[&asset.asset_uuid]),
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        traced_query(
+            "restore_asset",
+            tx.execute(
+                "INSERT INTO post_assets (post_id, asset_uuid, original_url, file_path, content_type, file_size, kind, created_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                &[post_id, &asset.asset_uuid, &asset.original_url, &asset.file_path,
+                  &asset.content_type, &asset.file_size, &asset.kind.as_str(), &SystemTime::from(asset.created_at)],
+            ),
+        )
+        .await?;
+        restored_assets.insert(format!("{}/{}", ASSETS_DIR, asset.file_path), asset.asset_uuid);
+        summary.assets_restored += 1;
+    }
+
+    // 第二次讀取：先寫入檔案再提交交易，避免資料庫指向不存在的檔案
+    let mut archive = open_archive(archive_path)?;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let Some(asset_uuid) = restored_assets.remove(&path) else {
+            continue;
+        };
+        let relative = Path::new(&path).strip_prefix(ASSETS_DIR)?;
+        let target = PathBuf::from(UPLOADS_DIR).join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+
+        // 封存檔可能來自其他來源，SVG 與下載時一樣先清理；無法清理的資源不還原
+        let row = traced_query(
+            "get_asset_content_type",
+            tx.query_one("SELECT content_type FROM post_assets WHERE asset_uuid = $1", &[&asset_uuid]),
+        )
+        .await?;
+        let content_type: Option<String> = row.get("content_type");
+        let mut sanitization = None;
+        if is_svg(&path, content_type.as_deref()) {
+            match svg_sanitizer::sanitize(&content) {
+                Ok(sanitized) => {
+                    sanitization = Some(sanitized.summary());
+                    content = sanitized.content;
+                }
+                Err(e) => {
+                    warn!(%asset_uuid, path = %path, error = %e, "skipping asset: SVG could not be sanitized");
+                    traced_query(
+                        "delete_asset",
+                        tx.execute("DELETE FROM post_assets WHERE asset_uuid = $1", &[&asset_uuid]),
+                    )
+                    .await?;
+                    summary.assets_restored -= 1;
+                    summary.assets_skipped += 1;
+                    continue;
+                }
+            }
+        }
+        fs::write(&target, &content)?;
+
+        // 尺寸與 checksum 以實際檔案內容重新計算，舊版封存檔也能補齊
+        let metadata = markdown_processor::asset_metadata(&content, content_type.as_deref());
+        traced_query(
+            "update_asset_metadata",
+            tx.execute(
+                "UPDATE post_assets SET file_size = $1, width = $2, height = $3, checksum = $4, sanitization = $5
+                 WHERE asset_uuid = $6",
+                &[&(content.len() as i64), &metadata.width, &metadata.height, &metadata.checksum, &sanitization, &asset_uuid],
+            ),
+        )
+        .await?;
+    }
+
+    // manifest 列出但封存檔中沒有檔案的資源不還原，避免資料庫指向不存在的檔案
+    for (path, asset_uuid) in &restored_assets {
+        warn!(%asset_uuid, path = %path, "skipping asset: file is missing from archive");
+        traced_query(
+            "delete_asset",
+            tx.execute("DELETE FROM post_assets WHERE asset_uuid = $1", &[asset_uuid]),
+        )
+        .await?;
+        summary.assets_restored -= 1;
+        summary.assets_skipped += 1;
+    }
+
+    tx.commit().await?;
+    if summary.posts_restored > 0 {
+        db::notify_posts_changed(&client, None).await?;
+    }
+    Ok(summary)
+}
+
+/// 資源路徑只能由一般的路徑元件組成（不可為絕對路徑或包含 `..`）
+fn is_safe_asset_path(file_path: &str) -> bool {
+    !file_path.is_empty() && Path::new(file_path).components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn is_svg(path: &str, content_type: Option<&str>) -> bool {
+    content_type.is_some_and(|content_type| content_type.starts_with("image/svg"))
+        || path.to_ascii_lowercase().ends_with(".svg")
+}
+
+fn open_archive(path: &str) -> Result<tar::Archive<zstd::Decoder<'static, io::BufReader<File>>>, Box<dyn Error + Send + Sync>> {
+    Ok(tar::Archive::new(zstd::Decoder::new(File::open(path)?)?))
+}
+
+fn append_bytes<W: io::Write>(builder: &mut tar::Builder<W>, path: &str, data: &[u8]) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+    );
+    header.set_cksum();
+    builder.append_data(&mut header, path, data)
+}
+
+fn invalid_archive(message: &str) -> Box<dyn Error + Send + Sync> {
+    Box::new(io::Error::new(io::ErrorKind::InvalidData, format!("Invalid archive: {}", message)))
+}
+
+fn conflict(message: &str) -> Box<dyn Error + Send + Sync> {
+    Box::new(io::Error::new(io::ErrorKind::AlreadyExists, format!("Restore aborted: {}", message)))
+}
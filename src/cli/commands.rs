@@ -1,17 +1,35 @@
 use deadpool_postgres::Pool;
+use serde::Serialize;
 use std::error::Error;
 use std::fs;
 use std::io::{self, Read};
+use tracing::{info, instrument};
 use uuid::Uuid;
 
-use crate::common::models::{Post, PostAsset};
-use crate::cli::markdown_processor;
+use crate::common::db;
+use crate::common::models::{Post, PostAsset, PostAssetResponse, PostListItem};
+use crate::common::telemetry::traced_query;
+use crate::cli::ingestion::{IngestionPolicy, UrlDecision};
+use crate::cli::{front_matter, markdown_processor};
+use crate::cli::markdown_processor::{DownloadedAsset, EmbeddedBlock, RejectedUrl};
 
 pub async fn add_post(
     pool: &Pool,
     title: &str,
     file_path: &str,
     api_base_url: Option<&str>,
+) -> Result<Uuid, Box<dyn Error + Send + Sync>> {
+    add_post_with_uuid(pool, None, title, file_path, api_base_url).await
+}
+
+/// 新增文章，指定 UUID 時沿用該 UUID（例如從 front matter 取得）
+#[instrument(skip(pool, api_base_url))]
+pub async fn add_post_with_uuid(
+    pool: &Pool,
+    uuid: Option<Uuid>,
+    title: &str,
+    file_path: &str,
+    api_base_url: Option<&str>,
 ) -> Result<Uuid, Box<dyn Error + Send + Sync>> {
     let mut content = String::new();
     fs::File::open(file_path)?.read_to_string(&mut content)?;
@@ -19,83 +37,104 @@ pub async fn add_post(
     let client = pool.get().await?;
     
     // 先建立 post 以取得 post_id
-    let row = client.query_one(
-        "INSERT INTO posts (title, content) VALUES ($1, $2) RETURNING id, uuid",
-        &[&title, &""],
+    let row = traced_query(
+        "create_post",
+        client.query_one(
+            "INSERT INTO posts (uuid, title, content) VALUES (COALESCE($1, gen_random_uuid()), $2, $3) RETURNING id, uuid",
+            &[&uuid, &title, &""],
+        ),
     ).await?;
     
     let post_id: i32 = row.get("id");
     let post_uuid: Uuid = row.get("uuid");
     
+    // front matter 不屬於文章內容
+    let (front_matter, body) = front_matter::split(&content)?;
+    let tags = front_matter::resolve_tags(front_matter.as_ref(), body);
+    
     // CLI 使用完整 URL（如果有設定）
-    let (processed_content, assets) = markdown_processor::process_markdown(&content, post_id, api_base_url).await?;
+    let policy = IngestionPolicy::from_env().with_front_matter(front_matter.as_ref());
+    let processed = markdown_processor::process_markdown(body, post_id, api_base_url, &policy).await?;
     
     // 更新 post 的內容
-    client.execute(
-        "UPDATE posts SET content = $1 WHERE id = $2",
-        &[&processed_content, &post_id],
+    traced_query(
+        "set_post_content",
+        client.execute(
+            "UPDATE posts SET content = $1, tags = $2 WHERE id = $3",
+            &[&processed.content, &tags, &post_id],
+        ),
     ).await?;
+    let summary = front_matter.as_ref().and_then(|fm| fm.summary.as_deref());
+    db::update_derived_fields(&client, post_id, &processed.content, summary).await?;
     
     // 儲存 assets 資訊到資料庫
-    for asset in assets {
-        client.execute(
-            "INSERT INTO post_assets (post_id, asset_uuid, original_url, file_path, content_type, file_size) 
-             VALUES ($1, $2, $3, $4, $5, $6)",
-            &[&post_id, &asset.asset_uuid, &asset.original_url, &asset.file_path, 
-              &asset.content_type, &asset.file_size],
+    for asset in processed.assets {
+        traced_query(
+            "insert_asset",
+            client.execute(
+                "INSERT INTO post_assets (post_id, asset_uuid, original_url, file_path, content_type, file_size, width, height, checksum, sanitization, kind) 
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+                &[&post_id, &asset.asset_uuid, &asset.original_url, &asset.file_path, 
+                  &asset.content_type, &asset.file_size, &asset.width, &asset.height, &asset.checksum, &asset.sanitization,
+                  &asset.kind.as_str()],
+            ),
         ).await?;
     }
     
+    db::notify_posts_changed(&client, Some(post_uuid)).await?;
+    info!(uuid = %post_uuid, "post created");
     Ok(post_uuid)
 }
 
-pub async fn list_posts(pool: &Pool, page: u32, limit: u32) -> Result<(), Box<dyn Error + Send + Sync>> {
+pub async fn list_posts(pool: &Pool, page: u32, limit: u32) -> Result<Vec<PostListItem>, Box<dyn Error + Send + Sync>> {
     let client = pool.get().await?;
     let offset = (page - 1) * limit;
-    let rows = client
-        .query(
-            "SELECT id, uuid, title, created_at FROM posts ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+    // 列表只需要摘要欄位，內文由 get 取得
+    let rows = traced_query(
+        "list_posts",
+        client.query(
+            "SELECT id, uuid, title, tags, excerpt, created_at, updated_at FROM posts ORDER BY created_at DESC LIMIT $1 OFFSET $2",
             &[&(limit as i64), &(offset as i64)],
-        )
-        .await?;
+        ),
+    )
+    .await?;
 
-    for row in rows {
-        let id: i32 = row.get("id");
-        let uuid: Uuid = row.get("uuid");
-        let title: String = row.get("title");
-        let created_at: std::time::SystemTime = row.get("created_at");
-        println!("ID: {}, UUID: {}, Title: {}, Created At: {:?}", id, uuid, title, created_at);
-    }
-    Ok(())
+    Ok(rows.into_iter().map(PostListItem::from).collect())
 }
 
-pub async fn get_post(pool: &Pool, uuid: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+pub async fn get_post(pool: &Pool, uuid: Uuid) -> Result<Post, Box<dyn Error + Send + Sync>> {
     let client = pool.get().await?;
-    let row = client
-        .query_one("SELECT id, uuid, title, content, created_at FROM posts WHERE uuid = $1", &[&uuid])
-        .await?;
-    let post = Post::from(row);
-    println!("ID: {}\nUUID: {}\nTitle: {}\nCreated At: {:?}\nContent:\n{}", 
-             post.id, post.uuid, post.title, post.created_at, post.content);
-    Ok(())
+    let row = traced_query(
+        "get_post",
+        client.query_one("SELECT id, uuid, title, content, tags, toc, summary, excerpt, word_count, reading_minutes, created_at, updated_at FROM posts WHERE uuid = $1", &[&uuid]),
+    )
+    .await?;
+    Ok(Post::from(row))
 }
 
+/// 更新文章，沒有提供任何欄位時回傳 false
+#[instrument(skip(pool, api_base_url))]
 pub async fn update_post(
     pool: &Pool,
     uuid: Uuid,
     title: Option<String>,
     file: Option<String>,
     api_base_url: Option<&str>,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
     let client = pool.get().await?;
     
     // 先取得 post_id
-    let row = client.query_one("SELECT id FROM posts WHERE uuid = $1", &[&uuid]).await?;
+    let row = traced_query(
+        "get_post_id",
+        client.query_one("SELECT id FROM posts WHERE uuid = $1", &[&uuid]),
+    )
+    .await?;
     let post_id: i32 = row.get("id");
     
     let mut updates = Vec::new();
     let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::new();
     let mut owned_strings: Vec<String> = Vec::new();
+    let mut owned_tags: Vec<Vec<String>> = Vec::new();
     let mut param_idx = 1;
 
     if let Some(t) = &title {
@@ -108,93 +147,130 @@ pub async fn update_post(
         let mut content = String::new();
         fs::File::open(f)?.read_to_string(&mut content)?;
         
+        let (front_matter, body) = front_matter::split(&content)?;
+        owned_tags.push(front_matter::resolve_tags(front_matter.as_ref(), body));
+        
         // 處理 markdown
-        let (processed_content, assets) = markdown_processor::process_markdown(&content, post_id, api_base_url).await?;
-        owned_strings.push(processed_content);
+        let policy = IngestionPolicy::from_env().with_front_matter(front_matter.as_ref());
+        let processed = markdown_processor::process_markdown(body, post_id, api_base_url, &policy).await?;
+        owned_strings.push(processed.content);
         updates.push(format!("content = ${}", param_idx));
         params.push(owned_strings.last().unwrap());
         param_idx += 1;
+        updates.push(format!("tags = ${}", param_idx));
+        params.push(owned_tags.last().unwrap());
+        param_idx += 1;
         
+        let summary = front_matter.as_ref().and_then(|fm| fm.summary.as_deref());
+        db::update_derived_fields(&client, post_id, owned_strings.last().unwrap(), summary).await?;
+
         // 刪除舊的 assets 記錄
-        client.execute("DELETE FROM post_assets WHERE post_id = $1", &[&post_id]).await?;
+        traced_query(
+            "delete_post_assets",
+            client.execute("DELETE FROM post_assets WHERE post_id = $1", &[&post_id]),
+        )
+        .await?;
         
         // 新增新的 assets
-        for asset in assets {
-            client.execute(
-                "INSERT INTO post_assets (post_id, asset_uuid, original_url, file_path, content_type, file_size) 
-                 VALUES ($1, $2, $3, $4, $5, $6)",
-                &[&post_id, &asset.asset_uuid, &asset.original_url, &asset.file_path, 
-                  &asset.content_type, &asset.file_size],
+        for asset in processed.assets {
+            traced_query(
+                "insert_asset",
+                client.execute(
+                    "INSERT INTO post_assets (post_id, asset_uuid, original_url, file_path, content_type, file_size, width, height, checksum, sanitization, kind) 
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+                    &[&post_id, &asset.asset_uuid, &asset.original_url, &asset.file_path, 
+                      &asset.content_type, &asset.file_size, &asset.width, &asset.height, &asset.checksum, &asset.sanitization,
+                      &asset.kind.as_str()],
+                ),
             ).await?;
         }
     }
 
     if updates.is_empty() {
-        println!("No updates provided for post UUID {}.", uuid);
-        return Ok(());
+        return Ok(false);
     }
 
     let query = format!(
-        "UPDATE posts SET {} WHERE uuid = ${}",
+        "UPDATE posts SET {}, updated_at = NOW() WHERE uuid = ${}",
         updates.join(", "),
         param_idx
     );
     params.push(&uuid);
 
-    client.execute(&query, &params.as_slice()).await?;
-    Ok(())
+    traced_query("update_post", client.execute(&query, params.as_slice())).await?;
+    db::notify_posts_changed(&client, Some(uuid)).await?;
+    info!("post updated");
+    Ok(true)
 }
 
+#[instrument(skip(pool))]
 pub async fn delete_post(pool: &Pool, uuid: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
     let client = pool.get().await?;
-    let result = client.execute("DELETE FROM posts WHERE uuid = $1", &[&uuid]).await?;
+    let result = traced_query(
+        "delete_post",
+        client.execute("DELETE FROM posts WHERE uuid = $1", &[&uuid]),
+    )
+    .await?;
     if result == 0 {
         return Err(Box::new(io::Error::new(io::ErrorKind::NotFound, format!("Post with UUID {} not found.", uuid))));
     }
+    db::notify_posts_changed(&client, Some(uuid)).await?;
+    info!("post deleted");
     Ok(())
 }
 
-pub async fn list_assets(pool: &Pool, uuid: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+pub async fn list_assets(pool: &Pool, uuid: Uuid, base_url: &str) -> Result<Vec<PostAssetResponse>, Box<dyn Error + Send + Sync>> {
     let client = pool.get().await?;
     
     // 先取得 post_id
-    let row = client.query_one("SELECT id FROM posts WHERE uuid = $1", &[&uuid]).await?;
+    let row = traced_query(
+        "get_post_id",
+        client.query_one("SELECT id FROM posts WHERE uuid = $1", &[&uuid]),
+    )
+    .await?;
     let post_id: i32 = row.get("id");
     
-    let rows = client.query(
-        "SELECT * FROM post_assets WHERE post_id = $1 ORDER BY created_at",
-        &[&post_id],
+    let rows = traced_query(
+        "list_post_assets",
+        client.query(
+            "SELECT * FROM post_assets WHERE post_id = $1 ORDER BY created_at",
+            &[&post_id],
+        ),
     ).await?;
     
-    println!("Assets for post {}:", uuid);
-    for row in rows {
-        let asset = PostAsset::from(row);
-        println!("\n  Asset UUID: {}", asset.asset_uuid);
-        println!("  Original URL: {}", asset.original_url);
-        println!("  File Path: {}", asset.file_path);
-        println!("  Content Type: {:?}", asset.content_type);
-        println!("  File Size: {:?} bytes", asset.file_size);
-    }
-    
-    Ok(())
+    Ok(rows
+        .into_iter()
+        .map(|row| PostAssetResponse::from_asset(PostAsset::from(row), base_url))
+        .collect())
+}
+
+/// test-markdown 的處理結果
+#[derive(Serialize, Debug)]
+pub struct MarkdownTestResult {
+    pub content: String,
+    pub assets: Vec<DownloadedAsset>,
+    /// 每個遠端 URL 是否下載及原因
+    pub decisions: Vec<UrlDecision>,
+    /// 下載失敗的 URL 與原因
+    pub rejected: Vec<RejectedUrl>,
+    /// 數學式與圖表，數學式無法轉換時附上原因
+    pub blocks: Vec<EmbeddedBlock>,
 }
 
-pub async fn test_markdown(file_path: &str, api_base_url: Option<&str>) -> Result<(), Box<dyn Error + Send + Sync>> {
+pub async fn test_markdown(file_path: &str, api_base_url: Option<&str>) -> Result<MarkdownTestResult, Box<dyn Error + Send + Sync>> {
     let mut content = String::new();
     fs::File::open(file_path)?.read_to_string(&mut content)?;
     
-    // 使用假的 post_id 進行測試
-    let (processed_content, assets) = markdown_processor::process_markdown(&content, 0, api_base_url).await?;
+    // 與 add 相同，只處理 front matter 之後的內容；使用假的 post_id 進行測試
+    let (front_matter, body) = front_matter::split(&content)?;
+    let policy = IngestionPolicy::from_env().with_front_matter(front_matter.as_ref());
+    let processed = markdown_processor::process_markdown(body, 0, api_base_url, &policy).await?;
     
-    println!("=== Processed Content ===\n{}\n", processed_content);
-    println!("=== Downloaded Assets ===");
-    for asset in assets {
-        println!("  - UUID: {}", asset.asset_uuid);
-        println!("    Original: {}", asset.original_url);
-        println!("    Path: {}", asset.file_path);
-        println!("    Type: {:?}", asset.content_type);
-        println!("    Size: {} bytes\n", asset.file_size);
-    }
-    
-    Ok(())
-}
\ No newline at end of file
+    Ok(MarkdownTestResult {
+        content: processed.content,
+        assets: processed.assets,
+        decisions: processed.decisions,
+        rejected: processed.rejected,
+        blocks: processed.blocks,
+    })
+}
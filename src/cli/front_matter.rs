@@ -0,0 +1,111 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+const DELIMITER: &str = "---";
+
+// 內文中的 **Tags:** `a` `b` 標記行
+static TAGS_LINE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^\*\*Tags:\*\*(.*)$").expect("invalid tags regex"));
+static TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`([^`]+)`").expect("invalid tag regex"));
+
+/// Markdown 檔案開頭的 YAML front matter
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct FrontMatter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "time::serde::rfc3339::option")]
+    pub created_at: Option<OffsetDateTime>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// 列表中顯示的摘要，未設定時取 `<!-- more -->` 之前的內容或內文開頭
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// 不下載的遠端 URL（保留原始連結）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_download: Vec<String>,
+}
+
+/// 拆出 front matter 與內文
+/// 沒有 front matter 時回傳 None 與原始內容
+pub fn split(content: &str) -> Result<(Option<FrontMatter>, &str), Box<dyn std::error::Error + Send + Sync>> {
+    let Some((yaml, body)) = split_raw(content) else {
+        return Ok((None, content));
+    };
+    let front_matter: FrontMatter = if yaml.trim().is_empty() {
+        FrontMatter::default()
+    } else {
+        serde_yaml::from_str(yaml)?
+    };
+    Ok((Some(front_matter), body))
+}
+
+/// 將 front matter 與內文組合成 markdown 檔案內容
+pub fn render(front_matter: &FrontMatter, body: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let yaml = serde_yaml::to_string(front_matter)?;
+    Ok(format!("{}\n{}{}\n{}", DELIMITER, yaml, DELIMITER, body))
+}
+
+/// 在 front matter 中寫入 `uuid`，保留其他欄位的原始格式
+/// 沒有 front matter 時會新增一個
+pub fn set_uuid(content: &str, uuid: Uuid) -> String {
+    let uuid_line = format!("uuid: {}", uuid);
+    let Some((yaml, body)) = split_raw(content) else {
+        return format!("{}\n{}\n{}\n{}", DELIMITER, uuid_line, DELIMITER, content);
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut replaced = false;
+    for line in yaml.lines() {
+        if line.starts_with("uuid:") {
+            lines.push(uuid_line.clone());
+            replaced = true;
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    if !replaced {
+        lines.insert(0, uuid_line);
+    }
+    format!("{}\n{}\n{}\n{}", DELIMITER, lines.join("\n"), DELIMITER, body)
+}
+
+/// 取得文章標籤：優先使用 front matter 的 `tags`，否則從內文的 `**Tags:**` 行擷取
+pub fn resolve_tags(front_matter: Option<&FrontMatter>, body: &str) -> Vec<String> {
+    if let Some(fm) = front_matter
+        && !fm.tags.is_empty()
+    {
+        return fm.tags.clone();
+    }
+
+    TAGS_LINE_RE
+        .captures(body)
+        .map(|caps| {
+            TAG_RE
+                .captures_iter(&caps[1])
+                .map(|tag| tag[1].trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 回傳 (yaml 區塊, 內文)，內容不是以 `---` 開頭時回傳 None
+fn split_raw(content: &str) -> Option<(&str, &str)> {
+    let rest = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == DELIMITER {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
@@ -0,0 +1,147 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use url::Url;
+
+use crate::cli::front_matter::FrontMatter;
+use crate::cli::url_guard::{env_list, host_matches};
+
+/// 加在 URL 後表示不下載的 fragment（`https://example.com/paper.pdf#nodownload`）
+pub const NO_DOWNLOAD_FRAGMENT: &str = "nodownload";
+
+/// markdown 中遠端 URL 的來源
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlKind {
+    Image,
+    Link,
+}
+
+/// 是否下載該 URL
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// 下載並把內容中的連結替換為資源
+    Download,
+    /// 保存快照，內容保留原始連結
+    Archive,
+    Skip,
+}
+
+impl fmt::Display for UrlKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UrlKind::Image => write!(f, "image"),
+            UrlKind::Link => write!(f, "link"),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Download => write!(f, "download"),
+            Action::Archive => write!(f, "archive"),
+            Action::Skip => write!(f, "skip"),
+        }
+    }
+}
+
+/// 單一 URL 的處理決定與原因，由 test-markdown 顯示
+#[derive(Serialize, Debug, Clone)]
+pub struct UrlDecision {
+    pub url: String,
+    pub kind: UrlKind,
+    pub action: Action,
+    pub reason: String,
+}
+
+/// 決定 markdown 中哪些遠端 URL 要下載
+///
+/// - 圖片一律下載
+/// - 連結只有在副檔名屬於 `ASSET_LINK_EXTENSIONS` 或主機屬於 `ASSET_LINK_HOSTS` 時下載（預設皆為空）
+/// - 其他連結在 `ARCHIVE_LINKS=true` 時保存快照（HTML 原始碼或 PDF），內容保留原始連結
+/// - URL 帶有 `#nodownload` 或列在 front matter 的 `no_download` 中時不下載也不保存快照
+#[derive(Debug, Clone, Default)]
+pub struct IngestionPolicy {
+    pub link_extensions: Vec<String>,
+    pub link_hosts: Vec<String>,
+    pub archive_links: bool,
+    pub no_download: HashSet<String>,
+}
+
+impl IngestionPolicy {
+    pub fn from_env() -> Self {
+        IngestionPolicy {
+            link_extensions: env_list("ASSET_LINK_EXTENSIONS")
+                .into_iter()
+                .map(|ext| ext.trim_start_matches('.').to_string())
+                .collect(),
+            link_hosts: env_list("ASSET_LINK_HOSTS"),
+            archive_links: std::env::var("ARCHIVE_LINKS")
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(false),
+            no_download: HashSet::new(),
+        }
+    }
+
+    /// 加入 front matter 中 `no_download` 列出的 URL
+    pub fn with_front_matter(mut self, front_matter: Option<&FrontMatter>) -> Self {
+        if let Some(fm) = front_matter {
+            self.no_download.extend(fm.no_download.iter().cloned());
+        }
+        self
+    }
+
+    pub fn decide(&self, url: &str, kind: UrlKind) -> UrlDecision {
+        let (action, reason) = self.evaluate(url, kind);
+        UrlDecision {
+            url: url.to_string(),
+            kind,
+            action,
+            reason,
+        }
+    }
+
+    fn evaluate(&self, url: &str, kind: UrlKind) -> (Action, String) {
+        let parsed = Url::parse(url).ok();
+        if parsed.as_ref().and_then(Url::fragment) == Some(NO_DOWNLOAD_FRAGMENT) {
+            return (Action::Skip, format!("#{} fragment", NO_DOWNLOAD_FRAGMENT));
+        }
+        if self.no_download.contains(url) {
+            return (Action::Skip, "listed in front matter no_download".to_string());
+        }
+        if kind == UrlKind::Image {
+            return (Action::Download, "image".to_string());
+        }
+
+        if let Some(url) = &parsed {
+            if let Some(ext) = extension(url)
+                && self.link_extensions.contains(&ext)
+            {
+                return (Action::Download, format!("link extension .{} is in ASSET_LINK_EXTENSIONS", ext));
+            }
+            if let Some(host) = url.host_str().map(str::to_ascii_lowercase)
+                && self.link_hosts.iter().any(|pattern| host_matches(pattern, &host))
+            {
+                return (Action::Download, format!("link host {} is in ASSET_LINK_HOSTS", host));
+            }
+        }
+        if self.archive_links {
+            return (Action::Archive, "link snapshot (ARCHIVE_LINKS)".to_string());
+        }
+        (Action::Skip, "links are only downloaded for ASSET_LINK_EXTENSIONS / ASSET_LINK_HOSTS".to_string())
+    }
+}
+
+/// 移除 URL 中的 `#nodownload`，沒有時回傳 None
+pub fn strip_no_download(url: &str) -> Option<&str> {
+    url.strip_suffix(NO_DOWNLOAD_FRAGMENT)?.strip_suffix('#')
+}
+
+fn extension(url: &Url) -> Option<String> {
+    let name = url.path_segments()?.next_back()?;
+    let (_, ext) = name.rsplit_once('.')?;
+    (!ext.is_empty()).then(|| ext.to_ascii_lowercase())
+}
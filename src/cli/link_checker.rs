@@ -0,0 +1,269 @@
+use deadpool_postgres::Pool;
+use futures_util::stream::{self, StreamExt};
+use pulldown_cmark::{Event, Parser, Tag};
+use reqwest::{header, Client, Method, StatusCode};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::io;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use url::Url;
+use uuid::Uuid;
+
+use crate::cli::markdown_processor::{is_asset_link, is_remote_url};
+use crate::cli::url_guard::{self, FetchPolicy};
+use crate::common::models::LinkStatus;
+use crate::common::render::markdown_options;
+use crate::common::telemetry::traced_query;
+
+// 單一請求的逾時
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+// 收到 429 時願意等待的 Retry-After 上限，超過時記錄為 rate_limited
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+const USER_AGENT: &str = concat!("journal-link-checker/", env!("CARGO_PKG_VERSION"));
+
+/// check-links 的選項
+#[derive(Debug, Clone)]
+pub struct LinkCheckOptions {
+    /// 要檢查的文章，空白代表全部
+    pub uuids: Vec<Uuid>,
+    /// 同時檢查的主機數
+    pub concurrency: usize,
+    /// 對同一主機連續請求的間隔
+    pub host_delay: Duration,
+}
+
+/// 單一連結的檢查結果
+#[derive(Serialize, Debug, Clone)]
+pub struct LinkResult {
+    pub url: String,
+    pub status: LinkStatus,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+/// 單篇文章的檢查結果，`problems` 只列出不是 ok 的連結
+#[derive(Serialize, Debug)]
+pub struct PostLinkReport {
+    pub uuid: Uuid,
+    pub title: String,
+    pub links: usize,
+    pub problems: Vec<LinkResult>,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct LinkCheckReport {
+    pub posts: Vec<PostLinkReport>,
+    /// 檢查的不重複 URL 數
+    pub checked: usize,
+    pub broken: usize,
+    pub rate_limited: usize,
+}
+
+/// 擷取文章中的外部連結與圖片（不含已下載、指向 asset API 的資源）
+pub fn extract_links(content: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    // 與渲染時相同的語法擴充：數學式中的文字不會被當成連結
+    Parser::new_ext(content, markdown_options())
+        .filter_map(|event| match event {
+            Event::Start(Tag::Link { dest_url, .. }) | Event::Start(Tag::Image { dest_url, .. }) => Some(dest_url),
+            _ => None,
+        })
+        .filter(|url| is_remote_url(url) && !is_asset_link(url))
+        .map(|url| url.to_string())
+        .filter(|url| seen.insert(url.clone()))
+        .collect()
+}
+
+/// 檢查文章中的連結並把結果寫入 link_checks
+/// 不同主機並行檢查，同一主機依序檢查並間隔 `host_delay`，避免觸發對方的流量限制
+pub async fn check_links(pool: &Pool, options: &LinkCheckOptions) -> Result<LinkCheckReport, Box<dyn Error + Send + Sync>> {
+    let client = pool.get().await?;
+    let rows = if options.uuids.is_empty() {
+        traced_query(
+            "list_posts",
+            client.query("SELECT id, uuid, title, content FROM posts ORDER BY created_at", &[]),
+        )
+        .await?
+    } else {
+        traced_query(
+            "list_posts_by_uuid",
+            client.query(
+                "SELECT id, uuid, title, content FROM posts WHERE uuid = ANY($1) ORDER BY created_at",
+                &[&options.uuids],
+            ),
+        )
+        .await?
+    };
+    if let Some(missing) = options
+        .uuids
+        .iter()
+        .find(|uuid| !rows.iter().any(|row| row.get::<_, Uuid>("uuid") == **uuid))
+    {
+        return Err(Box::new(io::Error::new(io::ErrorKind::NotFound, format!("Post with UUID {} not found.", missing))));
+    }
+
+    let posts: Vec<(i32, Uuid, String, Vec<String>)> = rows
+        .iter()
+        .map(|row| (row.get("id"), row.get("uuid"), row.get("title"), extract_links(row.get("content"))))
+        .collect();
+
+    // 依主機分組，同一 URL 只檢查一次
+    let mut by_host: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut unique = HashSet::new();
+    for url in posts.iter().flat_map(|(.., links)| links) {
+        if unique.insert(url.clone()) {
+            let host = Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+                .unwrap_or_default();
+            by_host.entry(host).or_default().push(url.clone());
+        }
+    }
+    info!(posts = posts.len(), urls = unique.len(), hosts = by_host.len(), "checking links");
+
+    let policy = FetchPolicy::from_env();
+    let http = policy
+        .client_builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(USER_AGENT)
+        .build()?;
+    let results: HashMap<String, LinkResult> = stream::iter(by_host.into_values())
+        .map(|urls| check_host(&http, &policy, urls, options.host_delay))
+        .buffer_unordered(options.concurrency.max(1))
+        .flat_map(stream::iter)
+        .map(|result| (result.url.clone(), result))
+        .collect()
+        .await;
+
+    let mut report = LinkCheckReport {
+        checked: results.len(),
+        broken: results.values().filter(|result| result.status.is_broken()).count(),
+        rate_limited: results.values().filter(|result| result.status == LinkStatus::RateLimited).count(),
+        ..Default::default()
+    };
+
+    for (post_id, uuid, title, links) in posts {
+        // 文章已不包含的連結不再保留檢查結果
+        traced_query(
+            "delete_stale_link_checks",
+            client.execute(
+                "DELETE FROM link_checks WHERE post_id = $1 AND NOT (url = ANY($2))",
+                &[&post_id, &links],
+            ),
+        )
+        .await?;
+
+        let mut problems = Vec::new();
+        for url in &links {
+            let result = &results[url];
+            traced_query(
+                "upsert_link_check",
+                client.execute(
+                    "INSERT INTO link_checks (post_id, url, status, status_code, error, checked_at, last_ok_at)
+                     VALUES ($1, $2, $3, $4, $5, NOW(), CASE WHEN $3 = 'ok' THEN NOW() END)
+                     ON CONFLICT (post_id, url) DO UPDATE SET
+                        status = EXCLUDED.status,
+                        status_code = EXCLUDED.status_code,
+                        error = EXCLUDED.error,
+                        checked_at = EXCLUDED.checked_at,
+                        last_ok_at = COALESCE(EXCLUDED.last_ok_at, link_checks.last_ok_at)",
+                    &[&post_id, url, &result.status.as_str(), &result.status_code, &result.error],
+                ),
+            )
+            .await?;
+            if result.status != LinkStatus::Ok {
+                problems.push(result.clone());
+            }
+        }
+        report.posts.push(PostLinkReport {
+            uuid,
+            title,
+            links: links.len(),
+            problems,
+        });
+    }
+
+    Ok(report)
+}
+
+/// 依序檢查同一主機的 URL
+async fn check_host(client: &Client, policy: &FetchPolicy, urls: Vec<String>, delay: Duration) -> Vec<LinkResult> {
+    let mut results = Vec::with_capacity(urls.len());
+    for (index, url) in urls.into_iter().enumerate() {
+        if index > 0 {
+            tokio::time::sleep(delay).await;
+        }
+        results.push(check_url(client, policy, url).await);
+    }
+    results
+}
+
+/// 先送 HEAD，失敗或不支援時改用 GET（只讀取標頭）
+/// IP 位址的 URL 不經過 DNS 解析，需在送出前以 `check_url` 檢查
+async fn check_url(client: &Client, policy: &FetchPolicy, url: String) -> LinkResult {
+    if let Err(reason) = Url::parse(&url).map_err(|e| e.to_string()).and_then(|parsed| {
+        policy.check_url(&parsed).map_err(|blocked| blocked.to_string())
+    }) {
+        return LinkResult {
+            url,
+            status: LinkStatus::Error,
+            status_code: None,
+            error: Some(reason),
+        };
+    }
+
+    let head = request(client, Method::HEAD, &url).await;
+    let outcome = match head {
+        Ok(status) if status.is_success() || status == StatusCode::TOO_MANY_REQUESTS => Ok(status),
+        _ => request(client, Method::GET, &url).await,
+    };
+
+    let (status, status_code, error) = match outcome {
+        Ok(code) if code.is_success() => (LinkStatus::Ok, Some(code), None),
+        Ok(StatusCode::TOO_MANY_REQUESTS) => (LinkStatus::RateLimited, Some(StatusCode::TOO_MANY_REQUESTS), None),
+        Ok(code) => (LinkStatus::Broken, Some(code), None),
+        Err(e) => (LinkStatus::Error, None, Some(e)),
+    };
+    debug!(%url, ?status, status_code = status_code.map(|code| code.as_u16()), "link checked");
+    LinkResult {
+        url,
+        status,
+        status_code: status_code.map(|code| i32::from(code.as_u16())),
+        error,
+    }
+}
+
+/// 送出請求並回傳最終狀態碼；收到 429 時依 Retry-After 等待後重試一次
+async fn request(client: &Client, method: Method, url: &str) -> Result<StatusCode, String> {
+    let mut retried = false;
+    loop {
+        let response = client
+            .request(method.clone(), url)
+            .send()
+            .await
+            .map_err(|e| url_guard::failure_reason(&e))?;
+        let status = response.status();
+        if status != StatusCode::TOO_MANY_REQUESTS || retried {
+            return Ok(status);
+        }
+
+        let retry_after = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        match retry_after {
+            Some(wait) if wait <= MAX_RETRY_AFTER => {
+                warn!(%url, wait_secs = wait.as_secs(), "rate limited; retrying after Retry-After");
+                tokio::time::sleep(wait).await;
+                retried = true;
+            }
+            _ => return Ok(status),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use pulldown_cmark::{Event, Parser, Tag};
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
 use reqwest::Client;
 use std::path::PathBuf;
 use tokio::fs::{self, File};
@@ -8,25 +8,136 @@ use uuid::Uuid;
 use futures_util::future::join_all;
 use std::collections::HashMap;
 use sha2::{Sha256, Digest};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::fmt;
+use std::sync::{Arc, LazyLock, Mutex};
+use tracing::{debug, error, instrument, warn, Instrument, Span};
 
-const UPLOADS_DIR: &str = "static/uploads";
+use crate::cli::ingestion::{self, Action, IngestionPolicy, UrlDecision, UrlKind};
+use crate::cli::svg_sanitizer;
+use crate::cli::url_guard::{self, FetchPolicy};
+use crate::common::highlight::fence_language;
+use crate::common::models::{asset_url, AssetKind};
+use crate::common::render::{markdown_options, math_to_mathml, MERMAID_LANGUAGE};
 
-#[derive(Debug, Clone)]
+pub const UPLOADS_DIR: &str = "static/uploads";
+
+// 比對內容中指向 asset API 的連結（可能帶有 base URL）
+static ASSET_LINK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?:https?://[^\s/()<>"']+)?/api/assets/([0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12})"#)
+        .expect("invalid asset link regex")
+});
+
+#[derive(Serialize, Debug, Clone)]
 pub struct DownloadedAsset {
     pub asset_uuid: Uuid,
     pub original_url: String,
     pub file_path: String,
     pub content_type: Option<String>,
     pub file_size: i64,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub checksum: String,
+    /// SVG 清理結果（`clean` 或 `sanitized: removed ...`），其他類型為 None
+    pub sanitization: Option<String>,
+    pub kind: AssetKind,
+}
+
+/// 從檔案內容計算的資源資訊
+pub(crate) struct AssetMetadata {
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub checksum: String,
+}
+
+/// 計算檔案的 SHA-256，圖片另外解析尺寸
+pub(crate) fn asset_metadata(content: &[u8], content_type: Option<&str>) -> AssetMetadata {
+    let dimensions = content_type
+        .filter(|content_type| content_type.starts_with("image/"))
+        .and_then(|_| imagesize::blob_size(content).ok())
+        .and_then(|size| Some((i32::try_from(size.width).ok()?, i32::try_from(size.height).ok()?)));
+
+    AssetMetadata {
+        width: dimensions.map(|(width, _)| width),
+        height: dimensions.map(|(_, height)| height),
+        checksum: format!("{:x}", Sha256::digest(content)),
+    }
+}
+
+/// 未能下載的 URL 與原因（被 SSRF 檢查封鎖、連線失敗或內容無法清理）
+#[derive(Serialize, Debug, Clone)]
+pub struct RejectedUrl {
+    pub url: String,
+    pub reason: String,
+}
+
+/// 數學式與圖表的種類
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    /// `$...$`
+    Math,
+    /// `$$...$$`
+    DisplayMath,
+    /// ```` ```mermaid ```` 區塊
+    Mermaid,
+}
+
+impl fmt::Display for BlockKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockKind::Math => write!(f, "math"),
+            BlockKind::DisplayMath => write!(f, "display_math"),
+            BlockKind::Mermaid => write!(f, "mermaid"),
+        }
+    }
+}
+
+/// markdown 中的數學式或圖表，渲染 HTML 時分別轉為 MathML 與 mermaid 佔位元素
+#[derive(Serialize, Debug, Clone)]
+pub struct EmbeddedBlock {
+    pub kind: BlockKind,
+    pub source: String,
+    /// 數學式無法轉為 MathML 的原因（渲染時會顯示原始碼）
+    pub error: Option<String>,
 }
 
+/// `process_markdown` 的結果
+#[derive(Debug)]
+pub struct ProcessedMarkdown {
+    /// 遠端連結已替換為 asset API 的內容
+    pub content: String,
+    pub assets: Vec<DownloadedAsset>,
+    /// 每個遠端 URL 是否下載及原因
+    pub decisions: Vec<UrlDecision>,
+    pub rejected: Vec<RejectedUrl>,
+    /// 依出現順序排列的數學式與圖表
+    pub blocks: Vec<EmbeddedBlock>,
+}
+
+/// 已下載資源的快取（原始 URL → asset），讓重複處理同一份內容時不必重新下載
+pub type AssetCache = Arc<Mutex<HashMap<String, DownloadedAsset>>>;
+
 /// 處理 markdown 內容並下載遠端資源
-/// 返回處理後的 markdown 和下載的資源列表
+/// 返回處理後的 markdown 和下載的資源列表，哪些 URL 要下載由 `policy` 決定
 pub async fn process_markdown(
     content: &str,
     post_id: i32,
     api_base_url: Option<&str>,
-) -> Result<(String, Vec<DownloadedAsset>), Box<dyn std::error::Error + Send + Sync>> {
+    policy: &IngestionPolicy,
+) -> Result<ProcessedMarkdown, Box<dyn std::error::Error + Send + Sync>> {
+    process_markdown_with_cache(content, post_id, api_base_url, policy, None).await
+}
+
+/// 與 `process_markdown` 相同，但優先使用快取中已下載的資源
+pub async fn process_markdown_with_cache(
+    content: &str,
+    post_id: i32,
+    api_base_url: Option<&str>,
+    policy: &IngestionPolicy,
+    cache: Option<&AssetCache>,
+) -> Result<ProcessedMarkdown, Box<dyn std::error::Error + Send + Sync>> {
     // 優先使用傳入的參數，否則嘗試從環境變數讀取
     let base_url = match api_base_url {
         Some(url) => url.to_string(),
@@ -35,76 +146,231 @@ pub async fn process_markdown(
     
     fs::create_dir_all(UPLOADS_DIR).await?;
 
-    let client = Client::new();
-    let parser = Parser::new(content);
+    let fetch_policy = FetchPolicy::from_env();
+    let client = fetch_policy.client()?;
+    // 與渲染時相同的語法擴充：數學式中的文字不會被當成連結
+    let parser = Parser::new_ext(content, markdown_options());
 
-    let mut download_futures = Vec::new();
-    let mut urls_to_download = std::collections::HashSet::new();
-
-    // 收集所有需要下載的遠端 URL
+    // 收集所有遠端 URL 並決定是否下載；同一 URL 同時作為圖片與連結時以圖片為準
+    let mut decisions: Vec<UrlDecision> = Vec::new();
+    let mut decision_index: HashMap<String, usize> = HashMap::new();
+    let mut blocks: Vec<EmbeddedBlock> = Vec::new();
+    let mut in_mermaid = false;
     for event in parser {
-        if let Event::Start(Tag::Image { dest_url, .. }) | Event::Start(Tag::Link { dest_url, .. }) = event {
-            if is_remote_url(&dest_url) && urls_to_download.insert(dest_url.to_string()) {
-                let client = client.clone();
-                let url = dest_url.to_string();
-                download_futures.push(tokio::spawn(async move {
-                    (url.clone(), download_and_save_file(&client, &url, post_id).await)
-                }));
+        let (dest_url, kind) = match event {
+            Event::Start(Tag::Image { dest_url, .. }) => (dest_url, UrlKind::Image),
+            Event::Start(Tag::Link { dest_url, .. }) => (dest_url, UrlKind::Link),
+            Event::InlineMath(source) => {
+                blocks.push(EmbeddedBlock {
+                    kind: BlockKind::Math,
+                    error: math_to_mathml(&source, false).err(),
+                    source: source.to_string(),
+                });
+                continue;
+            }
+            Event::DisplayMath(source) => {
+                blocks.push(EmbeddedBlock {
+                    kind: BlockKind::DisplayMath,
+                    error: math_to_mathml(&source, true).err(),
+                    source: source.to_string(),
+                });
+                continue;
+            }
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                in_mermaid = fence_language(&info) == Some(MERMAID_LANGUAGE);
+                if in_mermaid {
+                    blocks.push(EmbeddedBlock {
+                        kind: BlockKind::Mermaid,
+                        source: String::new(),
+                        error: None,
+                    });
+                }
+                continue;
+            }
+            Event::Text(text) if in_mermaid => {
+                if let Some(block) = blocks.last_mut() {
+                    block.source.push_str(&text);
+                }
+                continue;
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_mermaid = false;
+                continue;
+            }
+            _ => continue,
+        };
+        if !is_remote_url(&dest_url) {
+            continue;
+        }
+        match decision_index.get(dest_url.as_ref()) {
+            Some(&index) if decisions[index].kind == UrlKind::Link && kind == UrlKind::Image => {
+                decisions[index] = policy.decide(&dest_url, kind);
+            }
+            Some(_) => {}
+            None => {
+                decision_index.insert(dest_url.to_string(), decisions.len());
+                decisions.push(policy.decide(&dest_url, kind));
             }
         }
     }
 
+    let mut download_futures = Vec::new();
+    let mut download_urls = Vec::new();
+    let mut cached_assets: Vec<DownloadedAsset> = Vec::new();
+    for decision in &decisions {
+        let kind = match decision.action {
+            Action::Download => AssetKind::Inline,
+            Action::Archive => AssetKind::Archive,
+            Action::Skip => continue,
+        };
+        if let Some(asset) = cache.and_then(|cache| cache.lock().unwrap().get(&decision.url).cloned())
+            && asset.kind == kind
+        {
+            cached_assets.push(asset);
+            continue;
+        }
+        let client = client.clone();
+        let fetch_policy = fetch_policy.clone();
+        let url = decision.url.clone();
+        download_urls.push(url.clone());
+        download_futures.push(tokio::spawn(
+            async move { download_and_save_file(&client, &fetch_policy, &url, post_id, kind).await }
+                .in_current_span(),
+        ));
+    }
+
     // 等待所有下載任務完成
     let results = join_all(download_futures).await;
     
     let mut url_map: HashMap<String, String> = HashMap::new();
     let mut assets: Vec<DownloadedAsset> = Vec::new();
+    let mut rejected: Vec<RejectedUrl> = Vec::new();
     
-    for result in results {
+    // 快照不替換內容中的連結
+    for asset in cached_assets {
+        if asset.kind == AssetKind::Inline {
+            url_map.insert(asset.original_url.clone(), asset_url(&base_url, asset.asset_uuid));
+        }
+        assets.push(asset);
+    }
+    
+    for (original_url, result) in download_urls.into_iter().zip(results) {
         match result {
-            Ok((original_url, Ok(Some(asset)))) => {
-                // 使用完整 URL 或相對路徑
-                let api_path = if base_url.is_empty() {
-                    format!("/api/assets/{}", asset.asset_uuid)
-                } else {
-                    format!("{}/api/assets/{}", base_url, asset.asset_uuid)
-                };
-                url_map.insert(original_url, api_path);
+            Ok(Ok(asset)) => {
+                if let Some(cache) = cache {
+                    cache.lock().unwrap().insert(original_url.clone(), asset.clone());
+                }
+                if asset.kind == AssetKind::Inline {
+                    url_map.insert(original_url, asset_url(&base_url, asset.asset_uuid));
+                }
                 assets.push(asset);
             }
-            Err(join_error) => eprintln!("Download task failed: {}", join_error),
-            Ok((original_url, Err(e))) => eprintln!("Failed to download {}: {}", original_url, e),
-            _ => (),
+            Err(join_error) => {
+                error!(url = %original_url, error = %join_error, "download task failed");
+                rejected.push(RejectedUrl { url: original_url, reason: format!("download task failed: {}", join_error) });
+            }
+            Ok(Err(e)) => {
+                let reason = url_guard::failure_reason(e.as_ref());
+                warn!(url = %original_url, %reason, "failed to download asset");
+                rejected.push(RejectedUrl { url: original_url, reason });
+            }
         }
     }
 
     // 替換 URL
     let mut modified_content = content.to_string();
     for (original_url, new_path) in url_map {
-        modified_content = modified_content.replace(&original_url, &new_path);
+        modified_content = replace_url(&modified_content, &original_url, &new_path);
+    }
+    // 移除 opt-out 標記，保留原始連結
+    for decision in &decisions {
+        if let Some(stripped) = ingestion::strip_no_download(&decision.url) {
+            modified_content = replace_url(&modified_content, &decision.url, stripped);
+        }
+    }
+
+    Ok(ProcessedMarkdown {
+        content: modified_content,
+        assets,
+        decisions,
+        rejected,
+        blocks,
+    })
+}
+
+/// 將內容中的 `/api/assets/{uuid}` 連結替換為 `replace` 回傳的路徑
+/// `replace` 回傳 None 時保留原本的連結
+pub fn rewrite_asset_links<F>(content: &str, mut replace: F) -> String
+where
+    F: FnMut(Uuid) -> Option<String>,
+{
+    ASSET_LINK_RE
+        .replace_all(content, |caps: &Captures| {
+            Uuid::parse_str(&caps[1])
+                .ok()
+                .and_then(&mut replace)
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+/// 只替換完整的 URL：後面接著其他 URL 字元（例如 `#nodownload` 或較長的路徑）時不替換
+fn replace_url(content: &str, from: &str, to: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(index) = rest.find(from) {
+        let end = index + from.len();
+        let at_boundary = rest[end..]
+            .chars()
+            .next()
+            .is_none_or(|c| c.is_whitespace() || matches!(c, ')' | '>' | '<' | '"' | '\''));
+        result.push_str(&rest[..index]);
+        result.push_str(if at_boundary { to } else { from });
+        rest = &rest[end..];
     }
+    result.push_str(rest);
+    result
+}
 
-    Ok((modified_content, assets))
+/// 是否為指向 asset API 的連結（內容中已替換過的資源）
+pub fn is_asset_link(url: &str) -> bool {
+    ASSET_LINK_RE.is_match(url)
 }
 
-fn is_remote_url(url: &str) -> bool {
+pub(crate) fn is_remote_url(url: &str) -> bool {
     url.starts_with("http://") || url.starts_with("https://")
 }
 
+/// 不下載的回應（非 2xx、不支援的內容類型）或無效的 URL
+#[derive(Debug)]
+struct Unsupported(String);
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Unsupported {}
+
 /// 下載檔案並儲存到隨機目錄中
+/// 快照（`AssetKind::Archive`）另外接受 HTML 與純文字；不下載的原因以錯誤回傳，列入 `rejected`
+#[instrument(skip(client, policy), fields(status, content_type))]
 async fn download_and_save_file(
     client: &Client,
+    policy: &FetchPolicy,
     url_str: &str,
     post_id: i32,
-) -> Result<Option<DownloadedAsset>, Box<dyn std::error::Error + Send + Sync>> {
-    let url = match Url::parse(url_str) {
-        Ok(url) => url,
-        Err(_) => return Ok(None),
-    };
+    kind: AssetKind,
+) -> Result<DownloadedAsset, Box<dyn std::error::Error + Send + Sync>> {
+    let url = Url::parse(url_str).map_err(|e| Unsupported(format!("invalid URL: {}", e)))?;
+    // IP 位址的 URL 不經過 DNS 解析，需在送出前檢查
+    policy.check_url(&url)?;
 
     let response = client.get(url.clone()).send().await?;
+    Span::current().record("status", response.status().as_u16());
     if !response.status().is_success() {
-        return Ok(None);
+        return Err(Box::new(Unsupported(format!("HTTP {}", response.status()))));
     }
 
     let content_type = response.headers()
@@ -112,7 +378,7 @@ async fn download_and_save_file(
         .and_then(|value| value.to_str().ok())
         .and_then(|content_type| content_type.parse::<mime::Mime>().ok());
 
-    let (extension, mime_str) = match content_type {
+    let (extension, mime_str) = match &content_type {
         Some(m) => {
             let ext = match (m.type_(), m.subtype()) {
                 (mime::IMAGE, mime::JPEG) => Some("jpg"),
@@ -120,46 +386,72 @@ async fn download_and_save_file(
                 (mime::IMAGE, mime::GIF) => Some("gif"),
                 (mime::IMAGE, mime::SVG) => Some("svg"),
                 (mime::APPLICATION, mime::PDF) => Some("pdf"),
+                (mime::TEXT, mime::HTML) if kind == AssetKind::Archive => Some("html"),
+                (mime::APPLICATION, subtype) if kind == AssetKind::Archive && subtype == "xhtml" => Some("html"),
+                (mime::TEXT, mime::PLAIN) if kind == AssetKind::Archive => Some("txt"),
                 _ => None,
             };
+            Span::current().record("content_type", m.as_ref());
             (ext, Some(m.to_string()))
         }
         None => (None, None),
     };
 
-    if let Some(ext) = extension {
-        let content = response.bytes().await?;
-        let file_size = content.len() as i64;
-        
-        // 生成隨機目錄名稱（使用 URL 的 hash 確保同一來源的檔案在同一目錄）
-        let mut hasher = Sha256::new();
-        hasher.update(format!("{}:{}", post_id, url_str));
-        let hash = format!("{:x}", hasher.finalize());
-        let dir_name = &hash[..16]; // 取前 16 字元
-        
-        // 為檔案生成 UUID
-        let asset_uuid = Uuid::new_v4();
-        let filename = format!("{}.{}", asset_uuid, ext);
-        
-        // 建立目錄結構
-        let upload_dir = PathBuf::from(UPLOADS_DIR).join(dir_name);
-        fs::create_dir_all(&upload_dir).await?;
-        
-        let file_path = upload_dir.join(&filename);
-        let mut file = File::create(&file_path).await?;
-        file.write_all(&content).await?;
-
-        // 返回相對於 UPLOADS_DIR 的路徑
-        let relative_path = format!("{}/{}", dir_name, filename);
-        
-        Ok(Some(DownloadedAsset {
-            asset_uuid,
-            original_url: url_str.to_string(),
-            file_path: relative_path,
-            content_type: mime_str,
-            file_size,
-        }))
-    } else {
-        Ok(None)
+    let Some(ext) = extension else {
+        let reason = match content_type {
+            Some(m) => format!("unsupported content type {}", m.essence_str()),
+            None => "missing content type".to_string(),
+        };
+        return Err(Box::new(Unsupported(reason)));
+    };
+
+    let mut content = policy.read_body(response).await?;
+
+    // SVG 會從我們的網域提供，先移除 script 等內容；無法清理時不下載
+    let mut sanitization = None;
+    if ext == "svg" {
+        let sanitized = svg_sanitizer::sanitize(&content)?;
+        if !sanitized.removed.is_empty() {
+            warn!(result = %sanitized.summary(), "sanitized SVG asset");
+        }
+        sanitization = Some(sanitized.summary());
+        content = sanitized.content;
     }
+    let file_size = content.len() as i64;
+    
+    // 生成隨機目錄名稱（使用 URL 的 hash 確保同一來源的檔案在同一目錄）
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}:{}", post_id, url_str));
+    let hash = format!("{:x}", hasher.finalize());
+    let dir_name = &hash[..16]; // 取前 16 字元
+    
+    // 為檔案生成 UUID
+    let asset_uuid = Uuid::new_v4();
+    let filename = format!("{}.{}", asset_uuid, ext);
+    
+    // 建立目錄結構
+    let upload_dir = PathBuf::from(UPLOADS_DIR).join(dir_name);
+    fs::create_dir_all(&upload_dir).await?;
+    
+    let file_path = upload_dir.join(&filename);
+    let mut file = File::create(&file_path).await?;
+    file.write_all(&content).await?;
+
+    // 返回相對於 UPLOADS_DIR 的路徑
+    let relative_path = format!("{}/{}", dir_name, filename);
+    let metadata = asset_metadata(&content, mime_str.as_deref());
+    debug!(%asset_uuid, file_size, "asset downloaded");
+    
+    Ok(DownloadedAsset {
+        asset_uuid,
+        original_url: url_str.to_string(),
+        file_path: relative_path,
+        content_type: mime_str,
+        file_size,
+        width: metadata.width,
+        height: metadata.height,
+        checksum: metadata.checksum,
+        sanitization,
+        kind,
+    })
 }
\ No newline at end of file
@@ -1,2 +1,12 @@
+pub mod archive;
 pub mod commands;
-pub mod markdown_processor;
\ No newline at end of file
+pub mod front_matter;
+pub mod ingestion;
+pub mod link_checker;
+pub mod markdown_processor;
+pub mod output;
+pub mod site;
+pub mod svg_sanitizer;
+pub mod sync;
+pub mod url_guard;
+pub mod watch;
\ No newline at end of file
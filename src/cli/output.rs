@@ -0,0 +1,290 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::error::Error;
+use unicode_width::UnicodeWidthStr;
+use uuid::Uuid;
+
+use crate::cli::archive::{ExportSummary, RestoreSummary};
+use crate::cli::commands::MarkdownTestResult;
+use crate::cli::site::BuildSiteSummary;
+use crate::cli::link_checker::LinkCheckReport;
+use crate::cli::sync::SyncReport;
+use crate::common::models::{format_rfc3339, Post, PostAssetResponse, PostListItem};
+
+/// CLI 輸出格式
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 給人看的表格（時間為 RFC 3339）
+    #[default]
+    Table,
+    Json,
+    Yaml,
+}
+
+/// 可輸出的指令結果：JSON / YAML 直接使用 serde，表格格式由各型別自行決定
+pub trait Render: Serialize {
+    fn to_table(&self) -> String;
+}
+
+pub fn print<T: Render>(value: &T, format: OutputFormat) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match format {
+        OutputFormat::Table => println!("{}", value.to_table()),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+    }
+    Ok(())
+}
+
+/// 沒有專屬資料結構的指令（例如 delete、init-db）的結果
+#[derive(Serialize, Debug)]
+pub struct Message {
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<Uuid>,
+    pub message: String,
+}
+
+impl Message {
+    pub fn new(status: &'static str, uuid: Option<Uuid>, message: impl Into<String>) -> Self {
+        Message {
+            status,
+            uuid,
+            message: message.into(),
+        }
+    }
+}
+
+/// 以欄寬對齊的純文字表格，欄寬以顯示寬度計算（CJK 佔兩格）
+struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn new(headers: Vec<&'static str>) -> Self {
+        Table { headers, rows: Vec::new() }
+    }
+
+    fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    fn render(&self) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.width()).collect();
+        for row in &self.rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.width());
+            }
+        }
+
+        let format_row = |cells: Vec<&str>| -> String {
+            cells
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| format!("{}{}", cell, " ".repeat(widths[i] - cell.width())))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let mut lines = vec![format_row(self.headers.clone())];
+        let separators: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        lines.push(format_row(separators.iter().map(String::as_str).collect()));
+        for row in &self.rows {
+            lines.push(format_row(row.iter().map(String::as_str).collect()));
+        }
+        lines.join("\n")
+    }
+}
+
+/// 單筆資料以「欄位 / 值」兩欄呈現
+fn key_values(pairs: Vec<(&'static str, String)>) -> String {
+    let mut table = Table::new(vec!["FIELD", "VALUE"]);
+    for (key, value) in pairs {
+        table.row(vec![key.to_string(), value]);
+    }
+    table.render()
+}
+
+fn optional<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_else(|| "-".to_string())
+}
+
+impl Render for Message {
+    fn to_table(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl Render for Vec<PostListItem> {
+    fn to_table(&self) -> String {
+        let mut table = Table::new(vec!["ID", "UUID", "TITLE", "TAGS", "CREATED AT"]);
+        for post in self {
+            table.row(vec![
+                post.id.to_string(),
+                post.uuid.to_string(),
+                post.title.clone(),
+                post.tags.join(","),
+                format_rfc3339(post.created_at),
+            ]);
+        }
+        table.render()
+    }
+}
+
+impl Render for Post {
+    fn to_table(&self) -> String {
+        let fields = key_values(vec![
+            ("ID", self.id.to_string()),
+            ("UUID", self.uuid.to_string()),
+            ("TITLE", self.title.clone()),
+            ("TAGS", self.tags.join(",")),
+            ("CREATED AT", format_rfc3339(self.created_at)),
+            ("UPDATED AT", format_rfc3339(self.updated_at)),
+        ]);
+        format!("{}\n\n{}", fields, self.content)
+    }
+}
+
+fn dimensions(width: Option<i32>, height: Option<i32>) -> String {
+    match (width, height) {
+        (Some(width), Some(height)) => format!("{}x{}", width, height),
+        _ => "-".to_string(),
+    }
+}
+
+impl Render for Vec<PostAssetResponse> {
+    fn to_table(&self) -> String {
+        let mut table = Table::new(vec!["ASSET UUID", "KIND", "CONTENT TYPE", "SIZE", "DIMENSIONS", "CREATED AT", "URL"]);
+        for asset in self {
+            table.row(vec![
+                asset.asset_uuid.to_string(),
+                asset.kind.as_str().to_string(),
+                optional(&asset.content_type),
+                optional(&asset.file_size),
+                dimensions(asset.width, asset.height),
+                format_rfc3339(asset.created_at),
+                asset.url.clone(),
+            ]);
+        }
+        table.render()
+    }
+}
+
+impl Render for MarkdownTestResult {
+    fn to_table(&self) -> String {
+        let mut table = Table::new(vec!["ASSET UUID", "KIND", "CONTENT TYPE", "SIZE", "DIMENSIONS", "FILE PATH", "ORIGINAL URL"]);
+        for asset in &self.assets {
+            table.row(vec![
+                asset.asset_uuid.to_string(),
+                asset.kind.as_str().to_string(),
+                optional(&asset.content_type),
+                asset.file_size.to_string(),
+                dimensions(asset.width, asset.height),
+                asset.file_path.clone(),
+                asset.original_url.clone(),
+            ]);
+        }
+        let mut output = format!(
+            "=== Processed Content ===\n{}\n\n=== Downloaded Assets ===\n{}",
+            self.content,
+            table.render()
+        );
+        if !self.decisions.is_empty() {
+            let mut decisions = Table::new(vec!["URL", "KIND", "ACTION", "REASON"]);
+            for decision in &self.decisions {
+                decisions.row(vec![
+                    decision.url.clone(),
+                    decision.kind.to_string(),
+                    decision.action.to_string(),
+                    decision.reason.clone(),
+                ]);
+            }
+            output.push_str(&format!("\n\n=== URL Decisions ===\n{}", decisions.render()));
+        }
+        if !self.rejected.is_empty() {
+            let mut rejected = Table::new(vec!["URL", "REASON"]);
+            for item in &self.rejected {
+                rejected.row(vec![item.url.clone(), item.reason.clone()]);
+            }
+            output.push_str(&format!("\n\n=== Rejected URLs ===\n{}", rejected.render()));
+        }
+        if !self.blocks.is_empty() {
+            let mut blocks = Table::new(vec!["KIND", "STATUS", "SOURCE"]);
+            for block in &self.blocks {
+                blocks.row(vec![
+                    block.kind.to_string(),
+                    block.error.clone().unwrap_or_else(|| "ok".to_string()),
+                    block.source.split_whitespace().collect::<Vec<_>>().join(" "),
+                ]);
+            }
+            output.push_str(&format!("\n\n=== Math & Diagrams ===\n{}", blocks.render()));
+        }
+        output
+    }
+}
+
+impl Render for ExportSummary {
+    fn to_table(&self) -> String {
+        format!("Exported {} posts and {} assets", self.posts, self.assets)
+    }
+}
+
+impl Render for RestoreSummary {
+    fn to_table(&self) -> String {
+        format!(
+            "Restored {} posts ({} skipped) and {} assets ({} skipped)",
+            self.posts_restored, self.posts_skipped, self.assets_restored, self.assets_skipped
+        )
+    }
+}
+
+impl Render for BuildSiteSummary {
+    fn to_table(&self) -> String {
+        format!(
+            "Built site: {} posts, {} index pages, {} tags, {} assets",
+            self.posts, self.index_pages, self.tags, self.assets
+        )
+    }
+}
+
+impl Render for LinkCheckReport {
+    fn to_table(&self) -> String {
+        let mut table = Table::new(vec!["POST UUID", "TITLE", "STATUS", "CODE", "URL", "ERROR"]);
+        for post in &self.posts {
+            for link in &post.problems {
+                table.row(vec![
+                    post.uuid.to_string(),
+                    post.title.clone(),
+                    link.status.as_str().to_string(),
+                    link.status_code.map(|code| code.to_string()).unwrap_or_else(|| "-".to_string()),
+                    link.url.clone(),
+                    optional(&link.error),
+                ]);
+            }
+        }
+        let summary = format!(
+            "Checked {} links in {} posts: {} broken, {} rate limited",
+            self.checked,
+            self.posts.len(),
+            self.broken,
+            self.rate_limited
+        );
+        if self.broken + self.rate_limited == 0 {
+            summary
+        } else {
+            format!("{}\n\n{}", table.render(), summary)
+        }
+    }
+}
+
+impl Render for SyncReport {
+    fn to_table(&self) -> String {
+        let mut lines: Vec<String> = self.actions.iter().map(ToString::to_string).collect();
+        if !self.applied {
+            lines.push("Dry run: no changes applied.".to_string());
+        }
+        lines.join("\n")
+    }
+}
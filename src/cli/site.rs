@@ -0,0 +1,413 @@
+use deadpool_postgres::Pool;
+use regex::{Captures, Regex};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use time::OffsetDateTime;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::cli::markdown_processor::{self, UPLOADS_DIR};
+use crate::common::highlight;
+use crate::common::models::Post;
+use crate::common::render::{escape_html, render_html};
+use crate::common::telemetry::traced_query;
+
+const DEFAULT_LAYOUT: &str = include_str!("templates/layout.html");
+const DEFAULT_POST: &str = include_str!("templates/post.html");
+const DEFAULT_LIST: &str = include_str!("templates/list.html");
+
+// 樣板中的 {{ name }} 佔位符
+static PLACEHOLDER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([a-z_]+)\s*\}\}").expect("invalid placeholder regex"));
+
+pub struct BuildSiteOptions {
+    pub out: PathBuf,
+    /// 網站的公開網址，用於 feed 與 sitemap 的絕對連結
+    pub base_url: Option<String>,
+    /// 自訂樣板目錄，可覆寫 layout.html、post.html、list.html
+    pub templates: Option<PathBuf>,
+    pub per_page: usize,
+    pub site_title: String,
+    /// 程式碼配色，輸出為 highlight.css
+    pub highlight_theme: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct BuildSiteSummary {
+    pub posts: usize,
+    pub index_pages: usize,
+    pub tags: usize,
+    pub assets: usize,
+}
+
+struct Templates {
+    layout: String,
+    post: String,
+    list: String,
+}
+
+impl Templates {
+    fn load(dir: Option<&Path>) -> io::Result<Self> {
+        let read = |name: &str, default: &str| -> io::Result<String> {
+            match dir.map(|d| d.join(name)) {
+                Some(path) if path.exists() => fs::read_to_string(path),
+                _ => Ok(default.to_string()),
+            }
+        };
+        Ok(Templates {
+            layout: read("layout.html", DEFAULT_LAYOUT)?,
+            post: read("post.html", DEFAULT_POST)?,
+            list: read("list.html", DEFAULT_LIST)?,
+        })
+    }
+}
+
+/// 將所有文章輸出為可直接放在靜態檔案伺服器上的網站
+pub async fn build_site(pool: &Pool, options: &BuildSiteOptions) -> Result<BuildSiteSummary, Box<dyn Error + Send + Sync>> {
+    let templates = Templates::load(options.templates.as_deref())?;
+    let highlight_css = highlight::theme_css(&options.highlight_theme).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Unknown highlight theme '{}'; available: {}",
+                options.highlight_theme,
+                highlight::themes().join(", ")
+            ),
+        )
+    })?;
+    let client = pool.get().await?;
+
+    let posts: Vec<Post> = traced_query(
+        "list_posts",
+        client.query("SELECT id, uuid, title, content, tags, toc, summary, excerpt, word_count, reading_minutes, created_at, updated_at FROM posts ORDER BY created_at DESC", &[]),
+    )
+    .await?
+    .into_iter()
+    .map(Post::from)
+    .collect();
+
+    let asset_paths: HashMap<Uuid, String> = traced_query(
+        "list_assets",
+        client.query("SELECT asset_uuid, file_path FROM post_assets", &[]),
+    )
+    .await?
+    .into_iter()
+    .map(|row| (row.get("asset_uuid"), row.get("file_path")))
+    .collect();
+
+    let out = &options.out;
+    fs::create_dir_all(out.join("posts"))?;
+    fs::create_dir_all(out.join("tags"))?;
+    fs::write(out.join("highlight.css"), highlight_css)?;
+
+    let mut summary = BuildSiteSummary::default();
+    let mut referenced_assets: HashSet<Uuid> = HashSet::new();
+    let mut tags: BTreeMap<String, Vec<&Post>> = BTreeMap::new();
+    for post in &posts {
+        for tag in &post.tags {
+            tags.entry(tag.clone()).or_default().push(post);
+        }
+    }
+    let slugs = tag_slugs(tags.keys());
+
+    for post in &posts {
+        // 文章頁位於 posts/，asset 連結改為相對於網站根目錄的 assets/
+        let content = markdown_processor::rewrite_asset_links(&post.content, |uuid| {
+            asset_paths.get(&uuid).map(|file_path| {
+                referenced_assets.insert(uuid);
+                format!("../assets/{}", file_path)
+            })
+        });
+
+        let created_at = OffsetDateTime::from(post.created_at);
+        let tag_links: Vec<String> = post
+            .tags
+            .iter()
+            .map(|tag| format!("<a href=\"../tags/{}.html\">{}</a>", slugs[tag], escape_html(tag)))
+            .collect();
+
+        let body = fill(&templates.post, &[
+            ("title", &escape_html(&post.title)),
+            ("datetime", &created_at.format(&Rfc3339)?),
+            ("date", &format_date(created_at)),
+            ("tags", &tag_links.join(" ")),
+            ("content", &render_html(&content)),
+        ]);
+        let page = render_page(&templates, options, &post.title, "../", &body);
+        fs::write(out.join("posts").join(format!("{}.html", post.uuid)), page)?;
+        summary.posts += 1;
+    }
+
+    // 首頁與分頁：index.html、page/2.html ...
+    let per_page = options.per_page.max(1);
+    let chunks: Vec<&[Post]> = if posts.is_empty() {
+        vec![&[]]
+    } else {
+        posts.chunks(per_page).collect()
+    };
+    let total_pages = chunks.len();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let page_number = i + 1;
+        let root = if page_number == 1 { "" } else { "../" };
+        let refs: Vec<&Post> = chunk.iter().collect();
+        let body = fill(&templates.list, &[
+            ("heading", &escape_html(&options.site_title)),
+            ("items", &post_items(&refs, root)),
+            ("pagination", &pagination(page_number, total_pages, root)),
+        ]);
+        let page = render_page(&templates, options, &options.site_title, root, &body);
+        if page_number == 1 {
+            fs::write(out.join("index.html"), page)?;
+        } else {
+            fs::create_dir_all(out.join("page"))?;
+            fs::write(out.join("page").join(format!("{}.html", page_number)), page)?;
+        }
+        summary.index_pages += 1;
+    }
+
+    // 標籤頁：tags/index.html、tags/{slug}.html
+    let tag_items: Vec<String> = tags
+        .iter()
+        .map(|(tag, tagged)| {
+            format!(
+                "  <li><a href=\"{}.html\">{}</a> ({})</li>",
+                slugs[tag],
+                escape_html(tag),
+                tagged.len()
+            )
+        })
+        .collect();
+    let body = fill(&templates.list, &[
+        ("heading", "Tags"),
+        ("items", &tag_items.join("\n")),
+        ("pagination", ""),
+    ]);
+    fs::write(out.join("tags").join("index.html"), render_page(&templates, options, "Tags", "../", &body))?;
+
+    for (tag, tagged) in &tags {
+        let body = fill(&templates.list, &[
+            ("heading", &escape_html(tag)),
+            ("items", &post_items(tagged, "../")),
+            ("pagination", ""),
+        ]);
+        let page = render_page(&templates, options, tag, "../", &body);
+        fs::write(out.join("tags").join(format!("{}.html", slugs[tag])), page)?;
+        summary.tags += 1;
+    }
+
+    // feed 與 sitemap 需要絕對網址
+    match options.base_url.as_deref().map(|url| url.trim_end_matches('/')) {
+        Some(base_url) if !base_url.is_empty() => {
+            fs::write(out.join("feed.xml"), atom_feed(&posts, base_url, &options.site_title)?)?;
+            fs::write(out.join("rss.xml"), rss_feed(&posts, base_url, &options.site_title)?)?;
+            fs::write(out.join("sitemap.xml"), sitemap(&posts, &slugs, total_pages, base_url)?)?;
+        }
+        _ => warn!("no base URL given (--base-url or SITE_BASE_URL); skipping feeds and sitemap"),
+    }
+
+    // 只複製文章中實際引用到的 assets
+    for uuid in &referenced_assets {
+        let file_path = &asset_paths[uuid];
+        let source = PathBuf::from(UPLOADS_DIR).join(file_path);
+        if !source.is_file() {
+            warn!(asset_uuid = %uuid, path = %source.display(), "asset not found on disk");
+            continue;
+        }
+        let target = out.join("assets").join(file_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&source, &target)?;
+        summary.assets += 1;
+    }
+
+    Ok(summary)
+}
+
+/// 以 `{{ name }}` 佔位符填入樣板，未提供的變數替換為空字串
+fn fill(template: &str, vars: &[(&str, &str)]) -> String {
+    PLACEHOLDER_RE
+        .replace_all(template, |caps: &Captures| {
+            vars.iter()
+                .find(|(name, _)| *name == &caps[1])
+                .map(|(_, value)| value.to_string())
+                .unwrap_or_default()
+        })
+        .into_owned()
+}
+
+fn render_page(templates: &Templates, options: &BuildSiteOptions, title: &str, root: &str, content: &str) -> String {
+    fill(&templates.layout, &[
+        ("site_title", &escape_html(&options.site_title)),
+        ("page_title", &escape_html(title)),
+        ("root", root),
+        ("content", content),
+    ])
+}
+
+fn post_items(posts: &[&Post], root: &str) -> String {
+    posts
+        .iter()
+        .map(|post| {
+            format!(
+                "  <li><a href=\"{}posts/{}.html\">{}</a> <time>{}</time></li>",
+                root,
+                post.uuid,
+                escape_html(&post.title),
+                format_date(OffsetDateTime::from(post.created_at))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn pagination(page: usize, total_pages: usize, root: &str) -> String {
+    let page_href = |n: usize| {
+        if n == 1 {
+            format!("{}index.html", root)
+        } else {
+            format!("{}page/{}.html", root, n)
+        }
+    };
+    let mut links = Vec::new();
+    if page > 1 {
+        links.push(format!("<a href=\"{}\">&larr; Newer</a>", page_href(page - 1)));
+    }
+    if page < total_pages {
+        links.push(format!("<a href=\"{}\">Older &rarr;</a>", page_href(page + 1)));
+    }
+    links.join(" ")
+}
+
+/// 標籤轉為檔名：保留字母、數字（含 CJK），其餘字元以 `-` 取代
+fn tag_slug(tag: &str) -> String {
+    let slug: String = tag
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '-' })
+        .collect();
+    slug.trim_matches('-').to_string()
+}
+
+/// 每個標籤的檔名；不同標籤轉出相同的 slug（例如 `C++`、`C#` 與 `c`）時都加上短雜湊，
+/// 只有標點的標籤（slug 為空）直接以雜湊命名
+fn tag_slugs<'a>(tags: impl IntoIterator<Item = &'a String>) -> HashMap<String, String> {
+    let mut by_slug: HashMap<String, Vec<&String>> = HashMap::new();
+    for tag in tags {
+        by_slug.entry(tag_slug(tag)).or_default().push(tag);
+    }
+
+    let mut slugs = HashMap::new();
+    for (slug, tags) in by_slug {
+        let unique = tags.len() == 1 && !slug.is_empty();
+        for tag in tags {
+            let hash = &format!("{:x}", Sha256::digest(tag.as_bytes()))[..8];
+            let name = match (unique, slug.is_empty()) {
+                (true, _) => slug.clone(),
+                (false, true) => format!("tag-{}", hash),
+                (false, false) => format!("{}-{}", slug, hash),
+            };
+            slugs.insert(tag.clone(), name);
+        }
+    }
+    slugs
+}
+
+fn format_date(datetime: OffsetDateTime) -> String {
+    format!("{:04}-{:02}-{:02}", datetime.year(), u8::from(datetime.month()), datetime.day())
+}
+
+fn atom_feed(posts: &[Post], base_url: &str, site_title: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let updated = posts
+        .iter()
+        .map(|post| OffsetDateTime::from(post.updated_at))
+        .max()
+        .unwrap_or_else(OffsetDateTime::now_utc);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <title>{}</title>\n", escape_html(site_title)));
+    xml.push_str(&format!("  <link href=\"{}/\"/>\n", base_url));
+    xml.push_str(&format!("  <link rel=\"self\" href=\"{}/feed.xml\"/>\n", base_url));
+    xml.push_str(&format!("  <id>{}/</id>\n", base_url));
+    xml.push_str(&format!("  <updated>{}</updated>\n", updated.format(&Rfc3339)?));
+    for post in posts {
+        let created_at = OffsetDateTime::from(post.created_at).format(&Rfc3339)?;
+        let updated_at = OffsetDateTime::from(post.updated_at).format(&Rfc3339)?;
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", escape_html(&post.title)));
+        xml.push_str(&format!("    <link href=\"{}/posts/{}.html\"/>\n", base_url, post.uuid));
+        xml.push_str(&format!("    <id>urn:uuid:{}</id>\n", post.uuid));
+        xml.push_str(&format!("    <published>{}</published>\n", created_at));
+        xml.push_str(&format!("    <updated>{}</updated>\n", updated_at));
+        for tag in &post.tags {
+            xml.push_str(&format!("    <category term=\"{}\"/>\n", escape_html(tag)));
+        }
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    Ok(xml)
+}
+
+fn rss_feed(posts: &[Post], base_url: &str, site_title: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<rss version=\"2.0\">\n<channel>\n");
+    xml.push_str(&format!("  <title>{}</title>\n", escape_html(site_title)));
+    xml.push_str(&format!("  <link>{}/</link>\n", base_url));
+    xml.push_str(&format!("  <description>{}</description>\n", escape_html(site_title)));
+    for post in posts {
+        let link = format!("{}/posts/{}.html", base_url, post.uuid);
+        xml.push_str("  <item>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", escape_html(&post.title)));
+        xml.push_str(&format!("    <link>{}</link>\n", link));
+        xml.push_str(&format!("    <guid isPermaLink=\"false\">{}</guid>\n", post.uuid));
+        xml.push_str(&format!(
+            "    <pubDate>{}</pubDate>\n",
+            OffsetDateTime::from(post.created_at).format(&Rfc2822)?
+        ));
+        xml.push_str("  </item>\n");
+    }
+    xml.push_str("</channel>\n</rss>\n");
+    Ok(xml)
+}
+
+fn sitemap(
+    posts: &[Post],
+    tag_slugs: &HashMap<String, String>,
+    total_pages: usize,
+    base_url: &str,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let mut urls: Vec<(String, Option<String>)> = vec![(format!("{}/index.html", base_url), None)];
+    for n in 2..=total_pages {
+        urls.push((format!("{}/page/{}.html", base_url, n), None));
+    }
+    for post in posts {
+        let lastmod = OffsetDateTime::from(post.updated_at).format(&Rfc3339)?;
+        urls.push((format!("{}/posts/{}.html", base_url, post.uuid), Some(lastmod)));
+    }
+    urls.push((format!("{}/tags/index.html", base_url), None));
+    let mut slugs: Vec<&String> = tag_slugs.values().collect();
+    slugs.sort();
+    for slug in slugs {
+        urls.push((format!("{}/tags/{}.html", base_url, slug), None));
+    }
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for (loc, lastmod) in urls {
+        xml.push_str(&format!("  <url><loc>{}</loc>", escape_html(&loc)));
+        if let Some(lastmod) = lastmod {
+            xml.push_str(&format!("<lastmod>{}</lastmod>", lastmod));
+        }
+        xml.push_str("</url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    Ok(xml)
+}
@@ -0,0 +1,305 @@
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::{BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer, XmlVersion};
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::io;
+use std::sync::LazyLock;
+
+// 會執行程式或載入外部內容的元素，連同子元素一起移除
+const FORBIDDEN_ELEMENTS: &[&str] = &[
+    "script", "foreignobject", "iframe", "embed", "object", "audio", "video", "handler", "listener",
+];
+
+// 可用來把 href 動態改成 javascript: 的動畫元素
+const ANIMATION_ELEMENTS: &[&str] = &["set", "animate", "animatemotion", "animatetransform"];
+
+// 屬性值中出現即移除該屬性的 scheme
+const FORBIDDEN_SCHEMES: &[&str] = &["javascript:", "vbscript:", "data:text/html"];
+
+// href 允許的 data URL（內嵌點陣圖）
+const ALLOWED_DATA_URLS: &[&str] = &["data:image/png", "data:image/jpeg", "data:image/gif", "data:image/webp"];
+
+static CSS_URL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)url\(\s*['"]?\s*([^'")\s]*)"#).expect("invalid CSS url regex")
+});
+
+/// 清理後的 SVG
+pub struct SanitizedSvg {
+    pub content: Vec<u8>,
+    /// 被移除的元素（`<script>`）與屬性（`@onload`）
+    pub removed: BTreeSet<String>,
+}
+
+impl SanitizedSvg {
+    /// 記錄在 post_assets.sanitization 的結果摘要
+    pub fn summary(&self) -> String {
+        if self.removed.is_empty() {
+            "clean".to_string()
+        } else {
+            format!(
+                "sanitized: removed {}",
+                self.removed.iter().map(String::as_str).collect::<Vec<_>>().join(", ")
+            )
+        }
+    }
+}
+
+/// 移除 SVG 中的 script、事件處理屬性、外部參照與 foreignObject
+/// 無法解析、含有 DOCTYPE（實體展開）或根元素不是 `<svg>` 的檔案視為無法清理，回傳錯誤
+pub fn sanitize(input: &[u8]) -> Result<SanitizedSvg, Box<dyn Error + Send + Sync>> {
+    let source = std::str::from_utf8(input).map_err(|_| rejected("not valid UTF-8"))?;
+    let mut reader = Reader::from_str(source);
+    let mut writer = Writer::new(Vec::with_capacity(input.len()));
+    let mut removed = BTreeSet::new();
+    let mut seen_root = false;
+    // 目前位於被移除元素內的深度
+    let mut skip_depth = 0usize;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| rejected(&format!("invalid XML at byte {}: {}", reader.error_position(), e)))?;
+        match event {
+            Event::Eof => break,
+            Event::DocType(_) => return Err(rejected("DOCTYPE declarations are not allowed")),
+            _ if skip_depth > 0 => match event {
+                Event::Start(_) => skip_depth += 1,
+                Event::End(_) => skip_depth -= 1,
+                _ => {}
+            },
+            Event::Start(element) | Event::Empty(element) if !seen_root && local_name(&element) != "svg" => {
+                return Err(rejected(&format!("root element is <{}>, not <svg>", local_name(&element))));
+            }
+            Event::Start(element) => {
+                seen_root = true;
+                let name = local_name(&element);
+                if is_forbidden(&element, &name) {
+                    removed.insert(format!("<{}>", name));
+                    skip_depth = 1;
+                } else if name == "style" {
+                    // 含有外部參照的樣式表整段移除
+                    // 含有實體參照時無法直接檢查內容，視為不安全
+                    let css = reader.read_text(element.name())?;
+                    if !css.contains('&') && is_safe_css(&css) {
+                        writer.write_event(Event::Start(clean_element(&element, &mut removed)?))?;
+                        writer.write_event(Event::Text(BytesText::from_escaped(css.as_ref())))?;
+                        writer.write_event(Event::End(element.to_end()))?;
+                    } else {
+                        removed.insert("<style>".to_string());
+                    }
+                } else {
+                    writer.write_event(Event::Start(clean_element(&element, &mut removed)?))?;
+                }
+            }
+            Event::Empty(element) => {
+                seen_root = true;
+                let name = local_name(&element);
+                if is_forbidden(&element, &name) {
+                    removed.insert(format!("<{}>", name));
+                } else {
+                    writer.write_event(Event::Empty(clean_element(&element, &mut removed)?))?;
+                }
+            }
+            Event::PI(_) => {
+                removed.insert("processing instruction".to_string());
+            }
+            // 註解不影響顯示，直接捨棄
+            Event::Comment(_) => {}
+            other => writer.write_event(other)?,
+        }
+    }
+
+    if !seen_root {
+        return Err(rejected("no <svg> element"));
+    }
+
+    let content = if removed.is_empty() {
+        input.to_vec()
+    } else {
+        writer.into_inner()
+    };
+    Ok(SanitizedSvg { content, removed })
+}
+
+fn rejected(reason: &str) -> Box<dyn Error + Send + Sync> {
+    Box::new(io::Error::new(io::ErrorKind::InvalidData, format!("unsafe SVG: {}", reason)))
+}
+
+fn local_name(element: &BytesStart) -> String {
+    element.local_name().as_ref().to_ascii_lowercase()
+}
+
+fn is_forbidden(element: &BytesStart, name: &str) -> bool {
+    if FORBIDDEN_ELEMENTS.contains(&name) {
+        return true;
+    }
+    // <set attributeName="href" to="javascript:..."> 之類的動畫
+    ANIMATION_ELEMENTS.contains(&name)
+        && element.attributes().flatten().any(|attr| {
+            attr.key.local_name().as_ref().eq_ignore_ascii_case("attributeName")
+                && attr
+                    .normalized_value(XmlVersion::Implicit1_0)
+                    .is_ok_and(|target| is_href(&target) || target.to_ascii_lowercase().starts_with("on"))
+        })
+}
+
+fn is_href(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name == "href" || name.ends_with(":href")
+}
+
+/// 複製元素並移除不安全的屬性
+fn clean_element<'a>(
+    element: &BytesStart<'a>,
+    removed: &mut BTreeSet<String>,
+) -> Result<BytesStart<'a>, Box<dyn Error + Send + Sync>> {
+    let mut clean = element.clone();
+    clean.clear_attributes();
+    for attr in element.attributes() {
+        let attr = attr.map_err(|e| rejected(&format!("invalid attribute: {}", e)))?;
+        let name = attr.key.as_ref().to_string();
+        if is_safe_attribute(&name, &attr)? {
+            clean.push_attribute(attr);
+        } else {
+            removed.insert(format!("@{}", name.to_ascii_lowercase()));
+        }
+    }
+    Ok(clean)
+}
+
+fn is_safe_attribute(name: &str, attr: &Attribute) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let local = attr.key.local_name().as_ref().to_ascii_lowercase();
+    if local.starts_with("on") {
+        return Ok(false);
+    }
+
+    let value = attr
+        .normalized_value(XmlVersion::Implicit1_0)
+        .map_err(|e| rejected(&format!("invalid value for {}: {}", name, e)))?;
+    // 去除空白與控制字元，避免 "java\tscript:" 之類的繞過
+    let compact: String = value
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if FORBIDDEN_SCHEMES.iter().any(|scheme| compact.contains(scheme)) {
+        return Ok(false);
+    }
+
+    if is_href(name) {
+        return Ok(compact.starts_with('#') || ALLOWED_DATA_URLS.iter().any(|prefix| compact.starts_with(prefix)));
+    }
+    Ok(is_safe_css(&value))
+}
+
+/// CSS（style 屬性、<style>、fill="url(...)"）只允許參照文件內的 `#id`
+/// 含有 CSS 跳脫字元（例如 `u\72 l(`）時無法可靠判斷，一律視為不安全
+fn is_safe_css(css: &str) -> bool {
+    let lowered = css.to_ascii_lowercase();
+    if lowered.contains("@import") || lowered.contains("expression(") || lowered.contains('\\') {
+        return false;
+    }
+    CSS_URL_RE
+        .captures_iter(css)
+        .all(|captures| captures[1].starts_with('#'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clean(input: &str) -> (String, BTreeSet<String>) {
+        let svg = sanitize(input.as_bytes()).expect("sanitize failed");
+        (String::from_utf8(svg.content).unwrap(), svg.removed)
+    }
+
+    #[test]
+    fn removes_script_elements() {
+        let (content, removed) = clean(r#"<svg><script>alert(1)</script><rect/></svg>"#);
+        assert_eq!(content, "<svg><rect/></svg>");
+        assert!(removed.contains("<script>"));
+
+        let (content, removed) = clean(
+            r#"<svg xmlns:x="http://www.w3.org/2000/svg"><x:script>alert(1)</x:script><rect/></svg>"#,
+        );
+        assert!(!content.contains("alert"));
+        assert!(removed.contains("<script>"));
+    }
+
+    #[test]
+    fn removes_event_handlers() {
+        let (content, removed) = clean(r#"<svg onload="alert(1)"><rect/></svg>"#);
+        assert_eq!(content, "<svg><rect/></svg>");
+        assert!(removed.contains("@onload"));
+    }
+
+    #[test]
+    fn removes_javascript_links() {
+        let (content, removed) = clean(r#"<svg><a href="javascript:alert(1)"><rect/></a></svg>"#);
+        assert!(!content.contains("javascript"));
+        assert!(removed.contains("@href"));
+
+        let (content, removed) = clean(
+            r#"<svg xmlns:xlink="http://www.w3.org/1999/xlink"><a xlink:href="java&#9;script:alert(1)"><rect/></a></svg>"#,
+        );
+        assert!(!content.contains("script"));
+        assert!(removed.contains("@xlink:href"));
+    }
+
+    #[test]
+    fn removes_href_animations() {
+        let (content, removed) = clean(
+            r#"<svg><a><animate attributeName="href" values="javascript:alert(1)"/><rect/></a></svg>"#,
+        );
+        assert_eq!(content, "<svg><a><rect/></a></svg>");
+        assert!(removed.contains("<animate>"));
+    }
+
+    #[test]
+    fn removes_foreign_object() {
+        let (content, removed) = clean(
+            r#"<svg><foreignObject><iframe src="https://example.com"></iframe></foreignObject><rect/></svg>"#,
+        );
+        assert_eq!(content, "<svg><rect/></svg>");
+        assert!(removed.contains("<foreignobject>"));
+    }
+
+    #[test]
+    fn removes_external_css_urls() {
+        let (content, removed) = clean(r#"<svg><rect style="fill: url(http://example.com/x)"/></svg>"#);
+        assert_eq!(content, "<svg><rect/></svg>");
+        assert!(removed.contains("@style"));
+
+        let (content, removed) = clean(
+            r#"<svg><style>rect { fill: url('http://example.com/x') }</style><rect/></svg>"#,
+        );
+        assert_eq!(content, "<svg><rect/></svg>");
+        assert!(removed.contains("<style>"));
+
+        // 文件內的參照保留
+        let (_, removed) = clean(r##"<svg><style>rect { fill: url(#g) }</style><rect fill="url(#g)"/></svg>"##);
+        assert!(removed.is_empty());
+    }
+
+    #[test]
+    fn rejects_doctype_and_entities() {
+        let input = r#"<?xml version="1.0"?><!DOCTYPE svg [<!ENTITY x "y">]><svg>&x;</svg>"#;
+        assert!(sanitize(input.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn rejects_non_svg_root() {
+        assert!(sanitize(b"<html><svg/></html>").is_err());
+        assert!(sanitize(b"not xml at all").is_err());
+    }
+
+    #[test]
+    fn keeps_clean_input_byte_identical() {
+        let input = "<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox='0 0 10 10'>\n  <rect  width=\"10\" height=\"10\" fill=\"#fff\"/>\n</svg>\n";
+        let svg = sanitize(input.as_bytes()).unwrap();
+        assert_eq!(svg.content, input.as_bytes());
+        assert_eq!(svg.summary(), "clean");
+    }
+}
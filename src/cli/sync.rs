@@ -0,0 +1,392 @@
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::cli::commands;
+use crate::cli::front_matter::{self, FrontMatter};
+use crate::common::telemetry::traced_query;
+
+/// `--lockfile` 模式下記錄 檔案 → UUID 對應的檔案（位於內容目錄中）
+pub const LOCKFILE_NAME: &str = ".journal-sync.json";
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Lockfile {
+    posts: BTreeMap<String, Uuid>,
+}
+
+pub struct SyncOptions {
+    pub dir: PathBuf,
+    /// 刪除來源檔案已不存在的文章（只限先前由同一個內容目錄同步的文章）
+    pub delete: bool,
+    /// 只列出計畫，不寫入資料庫與檔案
+    pub dry_run: bool,
+    /// 使用 lockfile 記錄 UUID，而不是寫回 front matter
+    pub lockfile: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SyncAction {
+    Create { path: String, title: String, uuid: Option<Uuid> },
+    Update { path: String, title: String, uuid: Uuid },
+    Unchanged { path: String, uuid: Uuid },
+    Delete { uuid: Uuid, title: String, source_path: String },
+}
+
+impl fmt::Display for SyncAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncAction::Create { path, title, .. } => write!(f, "+ create    {} ({})", path, title),
+            SyncAction::Update { path, title, uuid } => write!(f, "~ update    {} ({}) -> {}", path, title, uuid),
+            SyncAction::Unchanged { path, uuid } => write!(f, "= unchanged {} -> {}", path, uuid),
+            SyncAction::Delete { uuid, title, source_path } => {
+                write!(f, "- delete    {} ({}) [{}]", uuid, title, source_path)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SyncReport {
+    pub actions: Vec<SyncAction>,
+    pub applied: bool,
+}
+
+/// 資料庫中已有的文章與其同步來源
+struct ExistingPost {
+    title: String,
+    source_root: Option<String>,
+    source_path: Option<String>,
+    source_hash: Option<String>,
+}
+
+struct SourceFile {
+    relative: String,
+    full: PathBuf,
+    uuid: Option<Uuid>,
+    title: String,
+    hash: String,
+}
+
+/// 將內容目錄中的 markdown 檔案同步到資料庫
+pub async fn sync(
+    pool: &Pool,
+    options: &SyncOptions,
+    api_base_url: Option<&str>,
+) -> Result<SyncReport, Box<dyn Error + Send + Sync>> {
+    let lockfile_path = options.dir.join(LOCKFILE_NAME);
+    let mut lockfile: Lockfile = if options.lockfile && lockfile_path.exists() {
+        serde_json::from_str(&fs::read_to_string(&lockfile_path)?)?
+    } else {
+        Lockfile::default()
+    };
+
+    // 以絕對路徑記錄內容目錄，區分由不同目錄同步的文章
+    let root = options.dir.canonicalize()?.to_string_lossy().into_owned();
+    let client = pool.get().await?;
+    let existing: HashMap<Uuid, ExistingPost> = traced_query(
+        "list_posts",
+        client.query("SELECT uuid, title, source_root, source_path, source_hash FROM posts", &[]),
+    )
+    .await?
+    .into_iter()
+    .map(|row| {
+            let post = ExistingPost {
+                title: row.get("title"),
+                source_root: row.get("source_root"),
+                source_path: row.get("source_path"),
+                source_hash: row.get("source_hash"),
+            };
+            (row.get("uuid"), post)
+        })
+        .collect();
+    let existing_by_path: HashMap<&str, Uuid> = existing
+        .iter()
+        .filter(|(_, post)| post.source_root.as_deref() == Some(root.as_str()))
+        .filter_map(|(uuid, post)| post.source_path.as_deref().map(|path| (path, *uuid)))
+        .collect();
+
+    let mut paths = Vec::new();
+    collect_markdown_files(&options.dir, &mut paths)?;
+    paths.sort();
+
+    let mut sources = Vec::new();
+    let mut seen_uuids: HashSet<Uuid> = HashSet::new();
+    for full in paths {
+        let relative = full
+            .strip_prefix(&options.dir)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        let content = fs::read_to_string(&full)?;
+        let (fm, body) = front_matter::split(&content)?;
+
+        let uuid = if options.lockfile {
+            // lockfile 沒有記錄時（例如上次同步中途失敗）以來源路徑找回先前建立的文章
+            match lockfile.posts.get(&relative) {
+                Some(uuid) => Some(*uuid),
+                None => existing_by_path.get(relative.as_str()).copied().inspect(|uuid| {
+                    lockfile.posts.insert(relative.clone(), *uuid);
+                }),
+            }
+        } else {
+            fm.as_ref().and_then(|fm| fm.uuid)
+        };
+        if let Some(uuid) = uuid
+            && !seen_uuids.insert(uuid)
+        {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("UUID {} is used by more than one file (second: {})", uuid, relative),
+            )));
+        }
+
+        let title = fm
+            .as_ref()
+            .and_then(|fm| fm.title.clone())
+            .or_else(|| first_heading(body))
+            .unwrap_or_else(|| {
+                full.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| relative.clone())
+            });
+
+        sources.push(SourceFile {
+            hash: source_hash(&title, fm.as_ref(), body),
+            relative,
+            full,
+            uuid,
+            title,
+        });
+    }
+
+    // 建立同步計畫
+    let mut actions: Vec<SyncAction> = sources.iter().map(|source| plan_action(source, &existing)).collect();
+
+    if options.delete {
+        for (uuid, post) in &existing {
+            if let Some(source_path) = &post.source_path
+                && post.source_root.as_deref() == Some(root.as_str())
+                && !seen_uuids.contains(uuid)
+            {
+                actions.push(SyncAction::Delete {
+                    uuid: *uuid,
+                    title: post.title.clone(),
+                    source_path: source_path.clone(),
+                });
+            }
+        }
+    }
+
+    if options.dry_run {
+        return Ok(SyncReport { actions, applied: false });
+    }
+
+    // 依計畫套用變更
+    let sources_by_path: HashMap<&str, &SourceFile> =
+        sources.iter().map(|source| (source.relative.as_str(), source)).collect();
+    for action in &mut actions {
+        match action {
+            SyncAction::Create { path, title, uuid } => {
+                let source = sources_by_path[path.as_str()];
+                let file = source.full.to_string_lossy();
+                let created = commands::add_post_with_uuid(pool, *uuid, title, &file, api_base_url).await?;
+                record_source(pool, created, &root, path, &source.hash).await?;
+
+                if options.lockfile {
+                    // 立即寫入，之後的步驟失敗時重新同步也不會重複建立
+                    lockfile.posts.insert(path.clone(), created);
+                    save_lockfile(&lockfile_path, &lockfile)?;
+                } else if uuid.is_none() {
+                    let content = fs::read_to_string(&source.full)?;
+                    fs::write(&source.full, front_matter::set_uuid(&content, created))?;
+                }
+                *uuid = Some(created);
+            }
+            SyncAction::Update { path, title, uuid } => {
+                let source = sources_by_path[path.as_str()];
+                let file = source.full.to_string_lossy().into_owned();
+                commands::update_post(pool, *uuid, Some(title.clone()), Some(file), api_base_url).await?;
+                record_source(pool, *uuid, &root, path, &source.hash).await?;
+            }
+            SyncAction::Unchanged { path, uuid } => {
+                // 檔案可能被搬移，僅更新來源路徑
+                let post = &existing[uuid];
+                if post.source_root.as_deref() != Some(root.as_str()) || post.source_path.as_deref() != Some(path.as_str()) {
+                    let source = sources_by_path[path.as_str()];
+                    record_source(pool, *uuid, &root, path, &source.hash).await?;
+                }
+            }
+            SyncAction::Delete { uuid, source_path, .. } => {
+                commands::delete_post(pool, *uuid).await?;
+                lockfile.posts.remove(source_path.as_str());
+            }
+        }
+    }
+
+    if options.lockfile {
+        // 移除已不存在的檔案
+        let current: HashSet<&str> = sources.iter().map(|source| source.relative.as_str()).collect();
+        lockfile.posts.retain(|path, _| current.contains(path.as_str()));
+        save_lockfile(&lockfile_path, &lockfile)?;
+    }
+
+    Ok(SyncReport { actions, applied: true })
+}
+
+/// 依內容雜湊決定來源檔案要建立、更新或維持不變
+fn plan_action(source: &SourceFile, existing: &HashMap<Uuid, ExistingPost>) -> SyncAction {
+    match source.uuid.and_then(|uuid| existing.get(&uuid).map(|post| (uuid, post))) {
+        Some((uuid, post)) if post.source_hash.as_deref() == Some(source.hash.as_str()) => SyncAction::Unchanged {
+            path: source.relative.clone(),
+            uuid,
+        },
+        Some((uuid, _)) => SyncAction::Update {
+            path: source.relative.clone(),
+            title: source.title.clone(),
+            uuid,
+        },
+        // 檔案帶有 UUID 但資料庫中沒有（例如新的資料庫）時沿用該 UUID
+        None => SyncAction::Create {
+            path: source.relative.clone(),
+            title: source.title.clone(),
+            uuid: source.uuid,
+        },
+    }
+}
+
+async fn record_source(
+    pool: &Pool,
+    uuid: Uuid,
+    root: &str,
+    path: &str,
+    hash: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let client = pool.get().await?;
+    traced_query(
+        "record_source",
+        client.execute(
+            "UPDATE posts SET source_root = $1, source_path = $2, source_hash = $3 WHERE uuid = $4",
+            &[&root, &path, &hash, &uuid],
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+fn save_lockfile(path: &Path, lockfile: &Lockfile) -> Result<(), Box<dyn Error + Send + Sync>> {
+    fs::write(path, serde_json::to_string_pretty(lockfile)?)?;
+    Ok(())
+}
+
+/// 內容雜湊包含標題、標籤、`summary`、`no_download` 與內文，不含 UUID，寫回 UUID 不會讓檔案被視為已變更
+fn source_hash(title: &str, fm: Option<&FrontMatter>, body: &str) -> String {
+    let tags = front_matter::resolve_tags(fm, body);
+    let summary = fm.and_then(|fm| fm.summary.as_deref()).unwrap_or_default();
+    let no_download = fm.map(|fm| fm.no_download.join("\n")).unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(title.as_bytes());
+    hasher.update([0]);
+    hasher.update(tags.join(",").as_bytes());
+    hasher.update([0]);
+    hasher.update(summary.as_bytes());
+    hasher.update([0]);
+    hasher.update(no_download.as_bytes());
+    hasher.update([0]);
+    hasher.update(body.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn first_heading(body: &str) -> Option<String> {
+    body.lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+/// 遞迴收集目錄中的 .md 檔案，略過隱藏檔與隱藏目錄
+pub(crate) fn collect_markdown_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_markdown_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: &str = "5f0c6a4e-2b7d-4c1e-9a3f-0d8e7b6a5c4b";
+
+    fn source(content: &str) -> SourceFile {
+        let (fm, body) = front_matter::split(content).unwrap();
+        SourceFile {
+            relative: "post.md".to_string(),
+            full: PathBuf::from("post.md"),
+            uuid: fm.as_ref().and_then(|fm| fm.uuid),
+            title: "Post".to_string(),
+            hash: source_hash("Post", fm.as_ref(), body),
+        }
+    }
+
+    fn synced(source: &SourceFile) -> HashMap<Uuid, ExistingPost> {
+        let post = ExistingPost {
+            title: source.title.clone(),
+            source_root: Some("/content".to_string()),
+            source_path: Some(source.relative.clone()),
+            source_hash: Some(source.hash.clone()),
+        };
+        HashMap::from([(source.uuid.unwrap(), post)])
+    }
+
+    #[test]
+    fn unchanged_file_is_unchanged() {
+        let content = format!("---\nuuid: {}\ntags: [a]\n---\nbody\n", UUID);
+        let existing = synced(&source(&content));
+        assert!(matches!(plan_action(&source(&content), &existing), SyncAction::Unchanged { .. }));
+    }
+
+    #[test]
+    fn writing_back_uuid_keeps_hash() {
+        let without = source("---\ntags: [a]\n---\nbody\n");
+        let with = source(&format!("---\nuuid: {}\ntags: [a]\n---\nbody\n", UUID));
+        assert_eq!(without.hash, with.hash);
+    }
+
+    #[test]
+    fn summary_change_is_update() {
+        let before = source(&format!("---\nuuid: {}\nsummary: Old summary\n---\nbody\n", UUID));
+        let after = source(&format!("---\nuuid: {}\nsummary: New summary\n---\nbody\n", UUID));
+        assert!(matches!(plan_action(&after, &synced(&before)), SyncAction::Update { .. }));
+
+        let removed = source(&format!("---\nuuid: {}\n---\nbody\n", UUID));
+        assert!(matches!(plan_action(&removed, &synced(&before)), SyncAction::Update { .. }));
+    }
+
+    #[test]
+    fn no_download_change_is_update() {
+        let before = source(&format!("---\nuuid: {}\n---\n![a](https://example.com/a.png)\n", UUID));
+        let after = source(&format!(
+            "---\nuuid: {}\nno_download: [https://example.com/a.png]\n---\n![a](https://example.com/a.png)\n",
+            UUID
+        ));
+        assert!(matches!(plan_action(&after, &synced(&before)), SyncAction::Update { .. }));
+    }
+}
@@ -0,0 +1,420 @@
+use futures_util::StreamExt;
+use ipnet::IpNet;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect;
+use reqwest::{Client, ClientBuilder, Response};
+use std::error::Error;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+use url::{Host, Url};
+
+// 最多跟隨的轉址次數（與 reqwest 預設相同）
+const MAX_REDIRECTS: usize = 10;
+
+// 下載的預設逾時（秒）與大小上限（bytes）
+const DEFAULT_DOWNLOAD_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_MAX_DOWNLOAD_BYTES: u64 = 20 * 1024 * 1024;
+
+/// 下載遠端資源前的檢查規則
+///
+/// - `ASSET_DENY_HOSTS`：禁止下載的主機（逗號分隔，`*.example.com` 包含子網域）
+/// - `ASSET_ALLOW_HOSTS`：設定後只允許下載這些主機
+/// - `ASSET_ALLOW_NETWORKS`：允許連線的內部網段（IP 或 CIDR），預設全部封鎖
+/// - `ASSET_DOWNLOAD_TIMEOUT_SECS`：每個下載請求（含讀取內容）的逾時
+/// - `ASSET_MAX_BYTES`：單一檔案的大小上限
+#[derive(Debug, Clone)]
+pub struct FetchPolicy {
+    pub allow_hosts: Vec<String>,
+    pub deny_hosts: Vec<String>,
+    pub allow_networks: Vec<IpNet>,
+    pub timeout: Duration,
+    pub max_bytes: u64,
+}
+
+impl Default for FetchPolicy {
+    fn default() -> Self {
+        FetchPolicy {
+            allow_hosts: Vec::new(),
+            deny_hosts: Vec::new(),
+            allow_networks: Vec::new(),
+            timeout: Duration::from_secs(DEFAULT_DOWNLOAD_TIMEOUT_SECS),
+            max_bytes: DEFAULT_MAX_DOWNLOAD_BYTES,
+        }
+    }
+}
+
+/// URL 被拒絕下載的原因
+#[derive(Debug)]
+pub struct Blocked(String);
+
+impl fmt::Display for Blocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "blocked: {}", self.0)
+    }
+}
+
+impl Error for Blocked {}
+
+impl FetchPolicy {
+    pub fn from_env() -> Self {
+        FetchPolicy {
+            allow_hosts: env_list("ASSET_ALLOW_HOSTS"),
+            deny_hosts: env_list("ASSET_DENY_HOSTS"),
+            allow_networks: parse_networks(&env_list("ASSET_ALLOW_NETWORKS")),
+            timeout: Duration::from_secs(env_number("ASSET_DOWNLOAD_TIMEOUT_SECS", DEFAULT_DOWNLOAD_TIMEOUT_SECS)),
+            max_bytes: env_number("ASSET_MAX_BYTES", DEFAULT_MAX_DOWNLOAD_BYTES),
+        }
+    }
+
+    /// 檢查 URL 的 scheme 與主機；主機為 IP 時一併檢查位址
+    /// 主機名稱對應的位址在連線時由 `GuardedResolver` 檢查
+    pub fn check_url(&self, url: &Url) -> Result<(), Blocked> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(Blocked(format!("scheme {} is not allowed", url.scheme())));
+        }
+        let host = url.host().ok_or_else(|| Blocked("URL has no host".to_string()))?;
+        let host_name = match &host {
+            Host::Domain(domain) => domain.trim_end_matches('.').to_ascii_lowercase(),
+            Host::Ipv4(ip) => ip.to_string(),
+            Host::Ipv6(ip) => ip.to_string(),
+        };
+
+        if self.deny_hosts.iter().any(|pattern| host_matches(pattern, &host_name)) {
+            return Err(Blocked(format!("host {} is in ASSET_DENY_HOSTS", host_name)));
+        }
+        if !self.allow_hosts.is_empty() && !self.allow_hosts.iter().any(|pattern| host_matches(pattern, &host_name)) {
+            return Err(Blocked(format!("host {} is not in ASSET_ALLOW_HOSTS", host_name)));
+        }
+
+        match host {
+            Host::Ipv4(ip) => self.check_ip(IpAddr::V4(ip)),
+            Host::Ipv6(ip) => self.check_ip(IpAddr::V6(ip)),
+            Host::Domain(_) => Ok(()),
+        }
+    }
+
+    /// 內部、loopback、link-local 與雲端 metadata 位址只有在 `ASSET_ALLOW_NETWORKS` 中才允許
+    pub fn check_ip(&self, ip: IpAddr) -> Result<(), Blocked> {
+        match restricted_range(ip) {
+            Some(range) if !self.allow_networks.iter().any(|network| network.contains(&ip)) => {
+                Err(Blocked(format!("{} is a {} address", ip, range)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 建立下載用的 HTTP client：DNS 解析與每次轉址都會經過檢查，並套用下載逾時
+    pub fn client(&self) -> Result<Client, Box<dyn Error + Send + Sync>> {
+        Ok(self.client_builder().timeout(self.timeout).build()?)
+    }
+
+    /// 讀取回應內容，超過 `max_bytes` 時停止讀取並回傳錯誤
+    /// 先檢查 Content-Length，未提供或不正確時在串流讀取中累計
+    pub async fn read_body(&self, response: Response) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        if let Some(length) = response.content_length().filter(|length| *length > self.max_bytes) {
+            return Err(Box::new(TooLarge { limit: self.max_bytes, size: Some(length) }));
+        }
+        let mut content = Vec::new();
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            if (content.len() + chunk.len()) as u64 > self.max_bytes {
+                return Err(Box::new(TooLarge { limit: self.max_bytes, size: None }));
+            }
+            content.extend_from_slice(&chunk);
+        }
+        Ok(content)
+    }
+
+    /// 套用檢查規則的 `ClientBuilder`，可再加上逾時等設定
+    /// 不使用系統 proxy，否則 DNS 解析會在 proxy 端進行而略過檢查
+    pub fn client_builder(&self) -> ClientBuilder {
+        let policy = Arc::new(self.clone());
+        let redirect_policy = {
+            let policy = policy.clone();
+            redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    return attempt.error(Blocked(format!("more than {} redirects", MAX_REDIRECTS)));
+                }
+                match policy.check_url(attempt.url()) {
+                    Ok(()) => attempt.follow(),
+                    Err(blocked) => {
+                        let reason = format!("redirect to {}: {}", attempt.url(), blocked.0);
+                        attempt.error(Blocked(reason))
+                    }
+                }
+            })
+        };
+        Client::builder()
+            .no_proxy()
+            .redirect(redirect_policy)
+            .dns_resolver(Arc::new(GuardedResolver { policy }))
+    }
+}
+
+/// 回應內容超過 `ASSET_MAX_BYTES`
+#[derive(Debug)]
+pub struct TooLarge {
+    limit: u64,
+    /// Content-Length；串流中途超過時為 None
+    size: Option<u64>,
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.size {
+            Some(size) => write!(f, "response is {} bytes, limit is {} bytes", size, self.limit),
+            None => write!(f, "response exceeds the {} byte limit", self.limit),
+        }
+    }
+}
+
+impl Error for TooLarge {}
+
+/// 從錯誤鏈中找出 `Blocked`（reqwest 會把 resolver 與轉址的錯誤包在自己的錯誤中）
+pub fn blocked_cause<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a Blocked> {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(blocked) = error.downcast_ref::<Blocked>() {
+            return Some(blocked);
+        }
+        current = error.source();
+    }
+    None
+}
+
+/// 下載或檢查失敗時顯示的原因
+/// reqwest 的錯誤訊息不含 resolver 的原因，優先顯示被封鎖的原因；其他錯誤加上所有來源錯誤
+pub fn failure_reason(error: &(dyn Error + 'static)) -> String {
+    if let Some(blocked) = blocked_cause(error) {
+        return blocked.to_string();
+    }
+    if error.downcast_ref::<reqwest::Error>().is_some_and(reqwest::Error::is_timeout) {
+        return "timed out".to_string();
+    }
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+/// 解析主機名稱後過濾受限制的位址；全部被過濾時拒絕連線
+struct GuardedResolver {
+    policy: Arc<FetchPolicy>,
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.policy.clone();
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let resolved: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            let mut blocked = None;
+            let allowed: Vec<SocketAddr> = resolved
+                .into_iter()
+                .filter(|addr| match policy.check_ip(addr.ip()) {
+                    Ok(()) => true,
+                    Err(reason) => {
+                        blocked.get_or_insert(reason);
+                        false
+                    }
+                })
+                .collect();
+
+            match (allowed.is_empty(), blocked) {
+                (true, Some(reason)) => {
+                    Err(Box::new(Blocked(format!("{} resolves to a restricted address ({})", host, reason.0))) as _)
+                },
+                _ => Ok(Box::new(allowed.into_iter()) as Addrs),
+            }
+        })
+    }
+}
+
+/// 回傳受限制位址的類型，公開位址回傳 None
+fn restricted_range(ip: IpAddr) -> Option<&'static str> {
+    match ip {
+        IpAddr::V4(ip) => restricted_v4(ip),
+        IpAddr::V6(ip) => {
+            // IPv4-mapped（::ffff:127.0.0.1）依 IPv4 規則判斷
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return restricted_v4(mapped);
+            }
+            restricted_v6(ip)
+        }
+    }
+}
+
+fn restricted_v4(ip: Ipv4Addr) -> Option<&'static str> {
+    let [a, b, ..] = ip.octets();
+    if ip == Ipv4Addr::new(169, 254, 169, 254) || ip == Ipv4Addr::new(100, 100, 100, 200) {
+        Some("cloud metadata")
+    } else if ip.is_loopback() {
+        Some("loopback")
+    } else if ip.is_private() {
+        Some("private")
+    } else if ip.is_link_local() {
+        Some("link-local")
+    } else if a == 100 && (64..128).contains(&b) {
+        Some("shared (CGNAT)")
+    } else if ip.is_unspecified() || a == 0 {
+        Some("unspecified")
+    } else if ip.is_broadcast() || ip.is_multicast() || a >= 240 {
+        Some("reserved")
+    } else {
+        None
+    }
+}
+
+fn restricted_v6(ip: Ipv6Addr) -> Option<&'static str> {
+    let first = ip.segments()[0];
+    if ip == Ipv6Addr::new(0xfd00, 0xec2, 0, 0, 0, 0, 0, 0x254) {
+        Some("cloud metadata")
+    } else if ip.is_loopback() {
+        Some("loopback")
+    } else if ip.is_unspecified() {
+        Some("unspecified")
+    } else if (first & 0xfe00) == 0xfc00 {
+        Some("private")
+    } else if (first & 0xffc0) == 0xfe80 {
+        Some("link-local")
+    } else if ip.is_multicast() {
+        Some("reserved")
+    } else {
+        None
+    }
+}
+
+/// `example.com` 只比對該主機，`*.example.com` 比對子網域
+pub(crate) fn host_matches(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host.ends_with(&format!(".{}", domain)),
+        None => host == pattern,
+    }
+}
+
+/// 解析 IP 或 CIDR，略過無效的項目
+fn parse_networks(items: &[String]) -> Vec<IpNet> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let network = item
+                .parse::<IpNet>()
+                .or_else(|_| item.parse::<IpAddr>().map(IpNet::from));
+            if network.is_err() {
+                warn!(value = %item, "ignoring invalid network in ASSET_ALLOW_NETWORKS");
+            }
+            network.ok()
+        })
+        .collect()
+}
+
+fn env_number(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+pub(crate) fn env_list(name: &str) -> Vec<String> {
+    std::env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(|item| item.trim().trim_end_matches('.').to_ascii_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn restricts_internal_ranges() {
+        for (address, range) in [
+            ("127.0.0.1", "loopback"),
+            ("127.255.0.9", "loopback"),
+            ("10.1.2.3", "private"),
+            ("172.16.0.1", "private"),
+            ("172.31.255.254", "private"),
+            ("192.168.1.1", "private"),
+            ("169.254.169.254", "cloud metadata"),
+            ("169.254.1.1", "link-local"),
+            ("100.64.0.1", "shared (CGNAT)"),
+            ("100.127.255.254", "shared (CGNAT)"),
+            ("::1", "loopback"),
+            ("fc00::1", "private"),
+            ("fdff::1", "private"),
+            ("fe80::1", "link-local"),
+            ("::ffff:127.0.0.1", "loopback"),
+            ("::ffff:10.0.0.1", "private"),
+        ] {
+            assert_eq!(restricted_range(ip(address)), Some(range), "{}", address);
+        }
+    }
+
+    #[test]
+    fn allows_public_addresses() {
+        for address in ["8.8.8.8", "172.32.0.1", "100.128.0.1", "2606:4700::1111", "::ffff:8.8.8.8"] {
+            assert_eq!(restricted_range(ip(address)), None, "{}", address);
+        }
+    }
+
+    #[test]
+    fn wildcard_matches_subdomains_only() {
+        assert!(host_matches("*.example.com", "cdn.example.com"));
+        assert!(host_matches("*.example.com", "a.b.example.com"));
+        assert!(!host_matches("*.example.com", "example.com"));
+        assert!(!host_matches("*.example.com", "badexample.com"));
+        assert!(host_matches("example.com", "example.com"));
+        assert!(!host_matches("example.com", "cdn.example.com"));
+    }
+
+    #[test]
+    fn rejects_non_http_schemes() {
+        let policy = FetchPolicy::default();
+        for url in ["file:///etc/passwd", "ftp://example.com/a", "gopher://example.com/", "data:text/plain,x"] {
+            assert!(policy.check_url(&Url::parse(url).unwrap()).is_err(), "{}", url);
+        }
+        assert!(policy.check_url(&Url::parse("https://example.com/a.png").unwrap()).is_ok());
+        assert!(policy.check_url(&Url::parse("http://127.0.0.1/a.png").unwrap()).is_err());
+        assert!(policy.check_url(&Url::parse("http://[::ffff:127.0.0.1]/a.png").unwrap()).is_err());
+    }
+
+    #[test]
+    fn host_lists_apply() {
+        let policy = FetchPolicy {
+            allow_hosts: vec!["*.example.com".to_string()],
+            deny_hosts: vec!["bad.example.com".to_string()],
+            ..FetchPolicy::default()
+        };
+        assert!(policy.check_url(&Url::parse("https://cdn.example.com/a").unwrap()).is_ok());
+        assert!(policy.check_url(&Url::parse("https://bad.example.com/a").unwrap()).is_err());
+        assert!(policy.check_url(&Url::parse("https://other.org/a").unwrap()).is_err());
+    }
+
+    #[test]
+    fn allow_networks_override_restrictions() {
+        let networks = parse_networks(&["10.0.0.0/8".to_string(), "192.168.1.5".to_string(), "bogus".to_string()]);
+        assert_eq!(networks.len(), 2);
+        let policy = FetchPolicy {
+            allow_networks: networks,
+            ..FetchPolicy::default()
+        };
+        assert!(policy.check_ip(ip("10.20.30.40")).is_ok());
+        assert!(policy.check_ip(ip("192.168.1.5")).is_ok());
+        assert!(policy.check_ip(ip("192.168.1.6")).is_err());
+        assert!(policy.check_ip(ip("127.0.0.1")).is_err());
+        assert!(policy.check_url(&Url::parse("http://10.0.0.1/a.png").unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,284 @@
+use actix_files::NamedFile;
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, Responder};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::cli::front_matter;
+use crate::cli::ingestion::IngestionPolicy;
+use crate::cli::markdown_processor::{self, AssetCache, UPLOADS_DIR};
+use crate::cli::sync::collect_markdown_files;
+use crate::common::highlight;
+use crate::common::render::{escape_html, render_html};
+
+// 連續存檔時等待事件平息再重新處理
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+const RELOAD_SCRIPT: &str = r#"<script>
+(function () {
+  let current = null;
+  setInterval(async function () {
+    try {
+      const version = await (await fetch('/__version')).text();
+      if (current !== null && version !== current) location.reload();
+      current = version;
+    } catch (e) {}
+  }, 1000);
+})();
+</script>"#;
+
+struct Page {
+    title: String,
+    html: String,
+}
+
+struct PreviewState {
+    root: PathBuf,
+    single_file: bool,
+    pages: RwLock<BTreeMap<String, Page>>,
+    version: AtomicU64,
+    cache: AssetCache,
+}
+
+/// 監看 markdown 檔案（或目錄），變更時重新處理並透過本機預覽伺服器顯示
+pub async fn watch(path: &Path, port: u16) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let root = path.canonicalize()?;
+    let single_file = root.is_file();
+    let state = web::Data::new(PreviewState {
+        root: root.clone(),
+        single_file,
+        pages: RwLock::new(BTreeMap::new()),
+        version: AtomicU64::new(0),
+        cache: Arc::new(Mutex::new(HashMap::new())),
+    });
+
+    let mut initial = Vec::new();
+    if single_file {
+        initial.push(root.clone());
+    } else {
+        collect_markdown_files(&root, &mut initial)?;
+    }
+    for file in &initial {
+        refresh(&state, file).await;
+    }
+
+    let server_state = state.clone();
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(server_state.clone())
+            .route("/", web::get().to(index))
+            .route("/__version", web::get().to(version))
+            .route("/__highlight.css", web::get().to(highlight_css))
+            .route("/preview/{path:.*}", web::get().to(preview))
+            .route("/api/assets/{uuid}", web::get().to(asset))
+    })
+    .workers(1)
+    .bind(("127.0.0.1", port))?
+    .run();
+    let handle = server.handle();
+    tokio::spawn(server);
+
+    println!("👀 Watching {}", root.display());
+    println!("📍 Preview: http://localhost:{}/", port);
+
+    // 單一檔案時監看上層目錄，編輯器以「寫入暫存檔再改名」存檔時才不會漏掉事件
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let _ = tx.send(event);
+    })?;
+    if single_file {
+        let parent = root.parent().unwrap_or(Path::new("."));
+        watcher.watch(parent, RecursiveMode::NonRecursive)?;
+    } else {
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+    }
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => {
+                let Some(event) = event else { break };
+                let mut changed: Vec<PathBuf> = Vec::new();
+                collect_changes(&state, event, &mut changed);
+
+                tokio::time::sleep(DEBOUNCE).await;
+                while let Ok(event) = rx.try_recv() {
+                    collect_changes(&state, event, &mut changed);
+                }
+
+                changed.sort();
+                changed.dedup();
+                for file in &changed {
+                    refresh(&state, file).await;
+                }
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    handle.stop(true).await;
+    Ok(())
+}
+
+fn collect_changes(state: &PreviewState, event: notify::Result<notify::Event>, changed: &mut Vec<PathBuf>) {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            error!(error = %e, "watch error");
+            return;
+        }
+    };
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    for path in event.paths {
+        let relevant = if state.single_file {
+            path == state.root
+        } else {
+            path.extension().is_some_and(|ext| ext == "md")
+        };
+        if relevant {
+            changed.push(path);
+        }
+    }
+}
+
+/// 重新處理單一檔案並通知瀏覽器重新載入
+async fn refresh(state: &PreviewState, file: &Path) {
+    let key = page_key(state, file);
+
+    if !file.exists() {
+        state.pages.write().unwrap().remove(&key);
+    } else {
+        let page = match render_file(file, &state.cache).await {
+            Ok(page) => page,
+            Err(e) => Page {
+                title: key.clone(),
+                html: format!("<pre>Failed to process {}: {}</pre>", escape_html(&key), escape_html(&e.to_string())),
+            },
+        };
+        println!("🔄 Rendered {}", key);
+        state.pages.write().unwrap().insert(key, page);
+    }
+    state.version.fetch_add(1, Ordering::SeqCst);
+}
+
+async fn render_file(file: &Path, cache: &AssetCache) -> Result<Page, Box<dyn Error + Send + Sync>> {
+    let content = tokio::fs::read_to_string(file).await?;
+    let (fm, body) = front_matter::split(&content)?;
+    let policy = IngestionPolicy::from_env().with_front_matter(fm.as_ref());
+    let title = fm
+        .and_then(|fm| fm.title)
+        .or_else(|| file.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+
+    // 使用相對的 /api/assets 路徑，由預覽伺服器提供檔案
+    let processed =
+        markdown_processor::process_markdown_with_cache(body, 0, Some(""), &policy, Some(cache)).await?;
+
+    Ok(Page {
+        title,
+        html: render_html(&processed.content),
+    })
+}
+
+fn page_key(state: &PreviewState, file: &Path) -> String {
+    if state.single_file {
+        return file
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+    }
+    file.strip_prefix(&state.root)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn render_document(title: &str, body: &str) -> HttpResponse {
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <link rel=\"stylesheet\" href=\"/__highlight.css\">\n\
+         <style>body {{ max-width: 46rem; margin: 0 auto; padding: 1rem; font-family: sans-serif; line-height: 1.6; }} \
+         img {{ max-width: 100%; }} pre {{ overflow-x: auto; padding: 0.75rem; background: #f5f5f5; }}</style>\n\
+         </head>\n<body>\n{}\n{}\n</body>\n</html>",
+        escape_html(title),
+        body,
+        RELOAD_SCRIPT
+    ))
+}
+
+async fn index(state: web::Data<PreviewState>) -> impl Responder {
+    let pages = state.pages.read().unwrap();
+    if state.single_file
+        && let Some(page) = pages.values().next()
+    {
+        return render_document(&page.title, &page.html);
+    }
+
+    let items: Vec<String> = pages
+        .iter()
+        .map(|(key, page)| {
+            format!(
+                "<li><a href=\"/preview/{}\">{}</a> <small>{}</small></li>",
+                escape_html(key),
+                escape_html(&page.title),
+                escape_html(key)
+            )
+        })
+        .collect();
+    render_document("Preview", &format!("<h1>Preview</h1>\n<ul>\n{}\n</ul>", items.join("\n")))
+}
+
+async fn preview(state: web::Data<PreviewState>, path: web::Path<String>) -> impl Responder {
+    let pages = state.pages.read().unwrap();
+    match pages.get(path.as_str()) {
+        Some(page) => render_document(&page.title, &page.html),
+        None => HttpResponse::NotFound().body("Page not found"),
+    }
+}
+
+async fn version(state: web::Data<PreviewState>) -> impl Responder {
+    HttpResponse::Ok().body(state.version.load(Ordering::SeqCst).to_string())
+}
+
+/// 程式碼配色，使用 HIGHLIGHT_THEME（未設定或不存在時使用預設配色）
+async fn highlight_css() -> impl Responder {
+    let css = std::env::var("HIGHLIGHT_THEME")
+        .ok()
+        .and_then(|theme| highlight::theme_css(&theme))
+        .or_else(|| highlight::theme_css(highlight::DEFAULT_THEME))
+        .unwrap_or_default();
+    HttpResponse::Ok().content_type("text/css; charset=utf-8").body(css)
+}
+
+async fn asset(state: web::Data<PreviewState>, uuid: web::Path<Uuid>, req: HttpRequest) -> HttpResponse {
+    let uuid = uuid.into_inner();
+    let found = state
+        .cache
+        .lock()
+        .unwrap()
+        .values()
+        .find(|asset| asset.asset_uuid == uuid)
+        .map(|asset| (asset.file_path.clone(), asset.content_type.clone()));
+
+    let Some((file_path, content_type)) = found else {
+        return HttpResponse::NotFound().body("Asset not found");
+    };
+
+    match NamedFile::open(PathBuf::from(UPLOADS_DIR).join(file_path)) {
+        Ok(mut file) => {
+            if let Some(ct) = content_type {
+                file = file.set_content_type(ct.parse::<mime::Mime>().unwrap_or(mime::APPLICATION_OCTET_STREAM));
+            }
+            file.into_response(&req)
+        }
+        Err(_) => HttpResponse::NotFound().body("File not found on disk"),
+    }
+}
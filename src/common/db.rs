@@ -1,19 +1,36 @@
-use deadpool_postgres::{Config, Pool, Runtime};
+use deadpool_postgres::{Config, GenericClient, Pool, Runtime};
+use tokio_postgres::types::Json;
 use tokio_postgres::NoTls;
+use uuid::Uuid;
+
+use crate::common::render::{table_of_contents, text_metadata};
+use crate::common::telemetry::traced_query;
+
+/// 文章新增、更新、刪除時發出 NOTIFY 的頻道，payload 為文章 UUID（空字串代表全部）
+pub const POSTS_CHANGED_CHANNEL: &str = "journal_posts_changed";
+
+/// 目前程式預期的資料庫結構版本，修改 `init_db` 的結構時一併遞增
+pub const SCHEMA_VERSION: i32 = 7;
+
+pub fn database_url() -> String {
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must be set")
+}
 
 pub fn create_pool() -> Pool {
     let mut cfg = Config::new();
-    let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    cfg.url = Some(db_url);
+    cfg.url = Some(database_url());
     cfg.create_pool(Some(Runtime::Tokio1), NoTls)
         .expect("Failed to create pool")
 }
 
 pub async fn init_db(pool: &Pool) {
     let client = pool.get().await.expect("Failed to get client from pool");
-    client
-        .batch_execute(
+    traced_query(
+        "create_schema",
+        client.batch_execute(
             "
+            DROP TABLE IF EXISTS schema_version;
+            DROP TABLE IF EXISTS link_checks;
             DROP TABLE IF EXISTS post_assets CASCADE;
             DROP TABLE IF EXISTS posts CASCADE;
             
@@ -23,7 +40,20 @@ pub async fn init_db(pool: &Pool) {
                 uuid UUID NOT NULL UNIQUE DEFAULT gen_random_uuid(),
                 title VARCHAR NOT NULL,
                 content TEXT NOT NULL,
-                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                tags TEXT[] NOT NULL DEFAULT '{}',
+                -- 標題目錄 [{level, text, id}]，寫入內容時一併產生
+                toc JSONB NOT NULL DEFAULT '[]',
+                -- front matter 的 summary；excerpt 優先使用它，其次為 <!-- more --> 之前的內容或內文開頭
+                summary TEXT,
+                excerpt TEXT NOT NULL DEFAULT '',
+                word_count INTEGER NOT NULL DEFAULT 0,
+                reading_minutes INTEGER NOT NULL DEFAULT 0,
+                -- 由 sync 指令管理的文章來源：內容目錄（絕對路徑）、相對於該目錄的路徑與內容雜湊
+                source_root TEXT,
+                source_path TEXT,
+                source_hash TEXT,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
             );
             
             -- 建立 uuid 索引以加速查詢
@@ -38,14 +68,86 @@ pub async fn init_db(pool: &Pool) {
                 file_path TEXT NOT NULL,
                 content_type VARCHAR(100),
                 file_size BIGINT,
+                -- 圖片尺寸（非圖片或無法解析時為 NULL）
+                width INTEGER,
+                height INTEGER,
+                -- 檔案內容的 SHA-256（hex）
+                checksum TEXT,
+                -- SVG 清理結果（clean / sanitized: removed ...），其他類型為 NULL
+                sanitization TEXT,
+                -- inline：內容中的連結已替換為此資源；archive：外部連結的快照
+                kind TEXT NOT NULL DEFAULT 'inline',
                 created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
             );
             
             -- 建立索引
             CREATE INDEX idx_post_assets_post_id ON post_assets(post_id);
             CREATE INDEX idx_post_assets_uuid ON post_assets(asset_uuid);
+
+            -- 文章連結的最近一次檢查結果（check-links 指令）
+            CREATE TABLE link_checks (
+                id SERIAL PRIMARY KEY,
+                post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+                url TEXT NOT NULL,
+                -- ok / broken / error / rate_limited
+                status TEXT NOT NULL,
+                status_code INTEGER,
+                error TEXT,
+                checked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                -- 最近一次檢查成功的時間，用於判斷失效多久
+                last_ok_at TIMESTAMPTZ,
+                UNIQUE (post_id, url)
+            );
+
+            CREATE INDEX idx_link_checks_status ON link_checks(status);
+
+            -- 資料庫結構版本，供 API 的 readiness 檢查比對
+            CREATE TABLE schema_version (
+                version INTEGER NOT NULL
+            );
         ",
-        )
-        .await
-        .expect("Failed to create database schema");
+        ),
+    )
+    .await
+    .expect("Failed to create database schema");
+    traced_query(
+        "record_schema_version",
+        client.execute("INSERT INTO schema_version (version) VALUES ($1)", &[&SCHEMA_VERSION]),
+    )
+    .await
+    .expect("Failed to record schema version");
+}
+
+/// 寫入由內容產生的欄位（目錄、摘要、字數與閱讀時間），內容變更時呼叫
+pub async fn update_derived_fields(
+    client: &impl GenericClient,
+    post_id: i32,
+    content: &str,
+    summary: Option<&str>,
+) -> Result<(), tokio_postgres::Error> {
+    let toc = Json(table_of_contents(content));
+    let metadata = text_metadata(content, summary);
+    traced_query(
+        "update_derived_fields",
+        client.execute(
+            "UPDATE posts SET toc = $1, summary = $2, excerpt = $3, word_count = $4, reading_minutes = $5 WHERE id = $6",
+            &[&toc, &summary, &metadata.excerpt, &metadata.word_count, &metadata.reading_minutes, &post_id],
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// 通知 API 實例文章已異動，使其快取失效
+pub async fn notify_posts_changed(
+    client: &tokio_postgres::Client,
+    uuid: Option<Uuid>,
+) -> Result<(), tokio_postgres::Error> {
+    let payload = uuid.map(|uuid| uuid.to_string()).unwrap_or_default();
+    traced_query(
+        "notify_posts_changed",
+        client.execute("SELECT pg_notify($1, $2)", &[&POSTS_CHANGED_CHANNEL, &payload]),
+    )
+    .await?;
+    Ok(())
 }
\ No newline at end of file
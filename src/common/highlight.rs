@@ -0,0 +1,73 @@
+use serde::Serialize;
+use std::sync::LazyLock;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+use utoipa::ToSchema;
+
+use crate::common::render::escape_html;
+
+/// 未指定時使用的配色
+pub const DEFAULT_THEME: &str = "InspiredGitHub";
+
+// 輸出的 class 加上前綴，避免與網站既有的樣式衝突；外層 <pre> 的 class 為 `hl-code`
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// 可以標示的語言
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct Language {
+    pub name: String,
+    /// 可用於 code fence 的名稱（副檔名），例如 `rs`
+    pub tokens: Vec<String>,
+}
+
+/// 從 code fence 的 info string 取出語言（`rust,ignore`、`c title="x"` 取第一個字）
+pub fn fence_language(info: &str) -> Option<&str> {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .next()
+        .filter(|lang| !lang.is_empty())
+}
+
+/// 以 class 標示程式碼，回傳完整的 `<pre class="hl-code"><code>` 區塊
+/// 語言不支援時回傳 None，由呼叫端輸出未標示的程式碼
+pub fn highlight(code: &str, lang: &str) -> Option<String> {
+    let syntax = SYNTAXES.find_syntax_by_token(lang)?;
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAXES, CLASS_STYLE);
+    for line in LinesWithEndings::from(code) {
+        generator.parse_html_for_line_which_includes_newline(line).ok()?;
+    }
+    Some(format!(
+        "<pre class=\"hl-code\"><code class=\"language-{}\">{}</code></pre>\n",
+        escape_html(lang),
+        generator.finalize()
+    ))
+}
+
+/// 依配色產生對應 `highlight` 輸出的 CSS，配色不存在時回傳 None
+pub fn theme_css(theme: &str) -> Option<String> {
+    css_for_theme_with_class_style(THEMES.themes.get(theme)?, CLASS_STYLE).ok()
+}
+
+/// 可用的配色名稱
+pub fn themes() -> Vec<&'static str> {
+    THEMES.themes.keys().map(String::as_str).collect()
+}
+
+/// 支援的語言，依名稱排序
+pub fn languages() -> Vec<Language> {
+    let mut languages: Vec<Language> = SYNTAXES
+        .syntaxes()
+        .iter()
+        .filter(|syntax| !syntax.hidden)
+        .map(|syntax| Language {
+            name: syntax.name.clone(),
+            tokens: syntax.file_extensions.clone(),
+        })
+        .collect();
+    languages.sort_by_key(|language| language.name.to_lowercase());
+    languages
+}
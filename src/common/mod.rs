@@ -1,2 +1,5 @@
 pub mod db;
-pub mod models;
\ No newline at end of file
+pub mod highlight;
+pub mod models;
+pub mod render;
+pub mod telemetry;
\ No newline at end of file
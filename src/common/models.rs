@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::time::SystemTime;
+use time::OffsetDateTime;
+use tokio_postgres::types::Json;
+use time::format_description::well_known::Rfc3339;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -8,16 +12,78 @@ pub struct Post {
     pub uuid: Uuid,
     pub title: String,
     pub content: String,
+    pub tags: Vec<String>,
+    pub toc: Vec<TocEntry>,
+    pub summary: Option<String>,
+    pub excerpt: String,
+    pub word_count: i32,
+    pub reading_minutes: i32,
+    #[serde(with = "rfc3339")]
     pub created_at: SystemTime,
+    #[serde(with = "rfc3339")]
+    pub updated_at: SystemTime,
 }
 
-// API Response 結構（不包含內部 ID）
+/// 文章列表的一列，不包含內文
 #[derive(Serialize, Debug)]
+pub struct PostListItem {
+    pub id: i32,
+    pub uuid: Uuid,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub excerpt: String,
+    #[serde(with = "rfc3339")]
+    pub created_at: SystemTime,
+    #[serde(with = "rfc3339")]
+    pub updated_at: SystemTime,
+}
+
+/// 目錄中的一個標題
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct TocEntry {
+    /// 標題層級（1–6）
+    pub level: u8,
+    pub text: String,
+    /// 渲染後 HTML 中標題的 id，可用於 `#id` 連結
+    pub id: String,
+}
+
+// API Response 結構（不包含內部 ID）
+#[derive(Serialize, Debug, ToSchema)]
 pub struct PostResponse {
     pub uuid: Uuid,
     pub title: String,
+    /// Markdown 原文，資源連結已改寫為 /api/assets/{uuid}
     pub content: String,
+    /// 依出現順序排列的標題
+    pub toc: Vec<TocEntry>,
+    /// 摘要：front matter 的 summary、`<!-- more -->` 之前的內容或純文字的開頭
+    pub excerpt: String,
+    /// 字數（中日文每個字計為一個字，不含程式碼區塊）
+    pub word_count: i32,
+    /// 預估閱讀時間（分鐘）
+    pub reading_minutes: i32,
+    #[schema(value_type = SystemTimeJson)]
     pub created_at: SystemTime,
+    #[schema(value_type = SystemTimeJson)]
+    pub updated_at: SystemTime,
+    /// 渲染後的 HTML（標題 id、程式碼語法標示），只在 `fields` 包含 html 時回傳
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    /// 標籤，只在 `include=tags` 時回傳
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// 文章的資源，只在 `include=assets` 時回傳
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assets: Option<Vec<PostAssetResponse>>,
+}
+
+/// API 回應中 `SystemTime` 的 JSON 格式（serde 預設），僅供 OpenAPI 文件使用
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct SystemTimeJson {
+    secs_since_epoch: u64,
+    nanos_since_epoch: u32,
 }
 
 impl From<Post> for PostResponse {
@@ -26,7 +92,15 @@ impl From<Post> for PostResponse {
             uuid: post.uuid,
             title: post.title,
             content: post.content,
+            toc: post.toc,
+            excerpt: post.excerpt,
+            word_count: post.word_count,
+            reading_minutes: post.reading_minutes,
             created_at: post.created_at,
+            updated_at: post.updated_at,
+            html: None,
+            tags: None,
+            assets: None,
         }
     }
 }
@@ -40,17 +114,190 @@ pub struct PostAsset {
     pub file_path: String,
     pub content_type: Option<String>,
     pub file_size: Option<i64>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub checksum: Option<String>,
+    pub sanitization: Option<String>,
+    pub kind: AssetKind,
+    #[serde(with = "rfc3339")]
     pub created_at: SystemTime,
 }
 
-#[derive(Deserialize)]
+/// 資源的用途，存放在 post_assets.kind
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind {
+    /// 內容中的連結已替換為此資源（圖片、允許下載的連結）
+    #[default]
+    Inline,
+    /// 外部連結的快照，內容保留原始連結
+    Archive,
+}
+
+impl AssetKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AssetKind::Inline => "inline",
+            AssetKind::Archive => "archive",
+        }
+    }
+
+    /// 從資料庫的值轉換，無法辨識時視為 inline
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "archive" => AssetKind::Archive,
+            _ => AssetKind::Inline,
+        }
+    }
+}
+
+// API 回應的資源資訊（不包含內部 ID、檔案路徑與原始來源）
+#[derive(Serialize, Debug, ToSchema)]
+pub struct PostAssetResponse {
+    pub asset_uuid: Uuid,
+    pub content_type: Option<String>,
+    pub file_size: Option<i64>,
+    /// 圖片寬度（像素），非圖片時為 null
+    pub width: Option<i32>,
+    /// 圖片高度（像素），非圖片時為 null
+    pub height: Option<i32>,
+    /// 檔案內容的 SHA-256（hex）
+    pub checksum: Option<String>,
+    pub kind: AssetKind,
+    /// 取得檔案的完整 URL
+    pub url: String,
+    #[schema(value_type = SystemTimeJson)]
+    pub created_at: SystemTime,
+}
+
+impl PostAssetResponse {
+    pub fn from_asset(asset: PostAsset, base_url: &str) -> Self {
+        PostAssetResponse {
+            url: asset_url(base_url, asset.asset_uuid),
+            asset_uuid: asset.asset_uuid,
+            content_type: asset.content_type,
+            file_size: asset.file_size,
+            width: asset.width,
+            height: asset.height,
+            checksum: asset.checksum,
+            kind: asset.kind,
+            created_at: asset.created_at,
+        }
+    }
+}
+
+/// 文章中的外部連結與其快照
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ReferenceResponse {
+    pub url: String,
+    /// 連結文字
+    pub text: String,
+    /// 匯入時保存的快照，沒有快照時為 null
+    pub archive: Option<ArchivedCopy>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ArchivedCopy {
+    pub asset_uuid: Uuid,
+    pub content_type: Option<String>,
+    pub file_size: Option<i64>,
+    /// 取得快照的完整 URL
+    pub url: String,
+    #[schema(value_type = SystemTimeJson)]
+    pub archived_at: SystemTime,
+}
+
+impl ArchivedCopy {
+    pub fn from_asset(asset: PostAsset, base_url: &str) -> Self {
+        ArchivedCopy {
+            url: asset_url(base_url, asset.asset_uuid),
+            asset_uuid: asset.asset_uuid,
+            content_type: asset.content_type,
+            file_size: asset.file_size,
+            archived_at: asset.created_at,
+        }
+    }
+}
+
+/// 未設定 API_BASE_URL 時使用的 API 位址
+pub const DEFAULT_API_BASE_URL: &str = "http://localhost:8080";
+
+/// 產生 asset 的 API 路徑，有 base URL 時使用完整 URL
+pub fn asset_url(base_url: &str, asset_uuid: Uuid) -> String {
+    if base_url.is_empty() {
+        format!("/api/assets/{}", asset_uuid)
+    } else {
+        format!("{}/api/assets/{}", base_url.trim_end_matches('/'), asset_uuid)
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct Pagination {
+    /// 頁碼，從 1 開始
     #[serde(default = "default_page")]
+    #[param(default = 1, minimum = 1)]
     pub page: u64,
+    /// 每頁筆數，超過上限時以上限計算
     #[serde(default = "default_limit")]
+    #[param(default = 10, minimum = 1, maximum = 100)]
     pub limit: u64,
 }
 
+/// 每頁筆數上限
+pub const MAX_PAGE_LIMIT: u64 = 100;
+
+impl Pagination {
+    /// 將頁碼與筆數限制在合理範圍（page 至少為 1，limit 介於 1 與 MAX_PAGE_LIMIT 之間）
+    pub fn clamped(self) -> Self {
+        Pagination {
+            page: self.page.max(1),
+            limit: self.limit.clamp(1, MAX_PAGE_LIMIT),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PostFieldsQuery {
+    /// 只回傳這些欄位（逗號分隔），例如 `uuid,title,excerpt`；未指定時回傳 html 以外的欄位
+    pub fields: Option<String>,
+    /// 一併回傳的關聯資料（逗號分隔）：`assets`、`tags`
+    pub include: Option<String>,
+}
+
+impl PostResponse {
+    /// 可用於 `include` 的關聯資料
+    pub const INCLUDES: &'static [&'static str] = &["assets", "tags"];
+
+    /// 可用於 `fields` 的欄位
+    pub const FIELDS: &'static [&'static str] = &[
+        "uuid",
+        "title",
+        "content",
+        "html",
+        "toc",
+        "excerpt",
+        "word_count",
+        "reading_minutes",
+        "created_at",
+        "updated_at",
+    ];
+
+    /// 未指定 `fields` 時回傳的欄位（html 需要另外指定）
+    pub const DEFAULT_FIELDS: &'static [&'static str] = &[
+        "uuid",
+        "title",
+        "content",
+        "toc",
+        "excerpt",
+        "word_count",
+        "reading_minutes",
+        "created_at",
+        "updated_at",
+    ];
+}
+
 fn default_page() -> u64 {
     1
 }
@@ -66,7 +313,28 @@ impl From<tokio_postgres::Row> for Post {
             uuid: row.get("uuid"),
             title: row.get("title"),
             content: row.get("content"),
+            tags: row.get("tags"),
+            toc: row.get::<_, Json<Vec<TocEntry>>>("toc").0,
+            summary: row.get("summary"),
+            excerpt: row.get("excerpt"),
+            word_count: row.get("word_count"),
+            reading_minutes: row.get("reading_minutes"),
             created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        }
+    }
+}
+
+impl From<tokio_postgres::Row> for PostListItem {
+    fn from(row: tokio_postgres::Row) -> Self {
+        PostListItem {
+            id: row.get("id"),
+            uuid: row.get("uuid"),
+            title: row.get("title"),
+            tags: row.get("tags"),
+            excerpt: row.get("excerpt"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
         }
     }
 }
@@ -81,7 +349,66 @@ impl From<tokio_postgres::Row> for PostAsset {
             file_path: row.get("file_path"),
             content_type: row.get("content_type"),
             file_size: row.get("file_size"),
+            width: row.get("width"),
+            height: row.get("height"),
+            checksum: row.get("checksum"),
+            sanitization: row.get("sanitization"),
+            kind: AssetKind::from_db(row.get("kind")),
             created_at: row.get("created_at"),
         }
     }
+}
+
+/// 連結檢查結果，存放在 link_checks.status
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkStatus {
+    Ok,
+    /// 伺服器回傳錯誤狀態碼（404、410、5xx…）
+    Broken,
+    /// 無法連線（DNS、TLS、逾時或被 SSRF 檢查封鎖）
+    Error,
+    /// 對方持續回傳 429，無法判斷
+    RateLimited,
+}
+
+impl LinkStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LinkStatus::Ok => "ok",
+            LinkStatus::Broken => "broken",
+            LinkStatus::Error => "error",
+            LinkStatus::RateLimited => "rate_limited",
+        }
+    }
+
+    pub fn is_broken(self) -> bool {
+        matches!(self, LinkStatus::Broken | LinkStatus::Error)
+    }
+}
+
+/// 以 RFC 3339 字串格式化時間
+pub fn format_rfc3339(time: SystemTime) -> String {
+    OffsetDateTime::from(time)
+        .format(&Rfc3339)
+        .unwrap_or_default()
+}
+
+/// `SystemTime` 以 RFC 3339 字串序列化（預設格式為 secs/nanos 結構）
+pub mod rfc3339 {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::SystemTime;
+    use time::OffsetDateTime;
+    use time::format_description::well_known::Rfc3339;
+
+    pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::format_rfc3339(*time))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        OffsetDateTime::parse(&value, &Rfc3339)
+            .map(SystemTime::from)
+            .map_err(serde::de::Error::custom)
+    }
 }
\ No newline at end of file
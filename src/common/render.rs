@@ -0,0 +1,473 @@
+use latex2mathml::{latex_to_mathml, DisplayStyle};
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::collections::HashSet;
+
+use crate::common::highlight::{fence_language, highlight};
+use crate::common::models::TocEntry;
+
+// latex2mathml 在輸出中標示語法錯誤的文字
+const MATH_PARSE_ERROR: &str = "[PARSE ERROR:";
+
+/// 圖表使用的 code fence 語言，輸出為 mermaid.js 可直接處理的佔位元素
+pub const MERMAID_LANGUAGE: &str = "mermaid";
+
+/// 將 markdown 轉為 HTML，標題加上與 `table_of_contents` 相同的 id
+/// 有指定語言的 code fence 以 class 標示語法（樣式見 `highlight::theme_css`），不支援的語言照原樣輸出
+/// `$...$` 與 `$$...$$` 轉為 MathML；mermaid 區塊輸出為保留原始碼的 `<pre class="mermaid">`
+/// 內容中的原始 HTML 不可信任，一律跳脫為文字；HTML 註解（例如 `<!-- more -->`）直接移除
+pub fn render_html(markdown: &str) -> String {
+    let mut ids = table_of_contents(markdown).into_iter().map(|entry| entry.id);
+    let mut events = Vec::new();
+    // 目前 code fence 的 info string 與內容，到區塊結尾時一次輸出
+    let mut code: Option<(CowStr, String)> = None;
+    // 目前原始 HTML 區塊的內容（每行一個事件），到區塊結尾時一次輸出
+    let mut html_block: Option<String> = None;
+    for event in Parser::new_ext(markdown, markdown_options()) {
+        match event {
+            Event::Start(Tag::Heading { level, classes, attrs, .. }) => events.push(Event::Start(Tag::Heading {
+                level,
+                id: ids.next().map(CowStr::from),
+                classes,
+                attrs,
+            })),
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) if fence_language(&info).is_some() => {
+                code = Some((info, String::new()));
+            }
+            Event::Text(text) if code.is_some() => {
+                if let Some((_, source)) = &mut code {
+                    source.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) if code.is_some() => {
+                let Some((info, source)) = code.take() else { continue };
+                if fence_language(&info) == Some(MERMAID_LANGUAGE) {
+                    events.push(Event::Html(format!("<pre class=\"mermaid\">{}</pre>\n", escape_html(&source)).into()));
+                    continue;
+                }
+                match fence_language(&info).and_then(|lang| highlight(&source, lang)) {
+                    Some(highlighted) => events.push(Event::Html(highlighted.into())),
+                    None => events.extend([
+                        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))),
+                        Event::Text(source.into()),
+                        Event::End(TagEnd::CodeBlock),
+                    ]),
+                }
+            }
+            Event::InlineMath(source) => events.push(Event::InlineHtml(render_math(&source, false).into())),
+            Event::DisplayMath(source) => events.push(Event::InlineHtml(render_math(&source, true).into())),
+            Event::Start(Tag::HtmlBlock) => html_block = Some(String::new()),
+            Event::Html(raw) if html_block.is_some() => {
+                if let Some(block) = &mut html_block {
+                    block.push_str(&raw);
+                }
+            }
+            Event::End(TagEnd::HtmlBlock) => {
+                if let Some(block) = html_block.take().filter(|block| !is_html_comment(block)) {
+                    events.extend([
+                        Event::Start(Tag::Paragraph),
+                        Event::Text(block.trim_end().to_string().into()),
+                        Event::End(TagEnd::Paragraph),
+                    ]);
+                }
+            }
+            Event::Html(raw) | Event::InlineHtml(raw) => {
+                if !is_html_comment(&raw) {
+                    events.push(Event::Text(raw));
+                }
+            }
+            other => events.push(other),
+        }
+    }
+    let mut output = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut output, events.into_iter());
+    output
+}
+
+fn is_html_comment(raw: &str) -> bool {
+    let raw = raw.trim();
+    raw.starts_with("<!--") && raw.ends_with("-->")
+}
+
+/// 將 LaTeX 轉為 MathML，`display` 為 `$$...$$` 區塊
+pub fn math_to_mathml(source: &str, display: bool) -> Result<String, String> {
+    let style = if display { DisplayStyle::Block } else { DisplayStyle::Inline };
+    let mathml = latex_to_mathml(source, style).map_err(|e| e.to_string())?;
+    // 部分語法錯誤不會回傳 Err，而是以 <mtext>[PARSE ERROR: ...]</mtext> 嵌在輸出中
+    match mathml.split_once(MATH_PARSE_ERROR) {
+        Some((_, rest)) => Err(rest.split_once(']').map_or(rest, |(reason, _)| reason).trim().to_string()),
+        None => Ok(mathml),
+    }
+}
+
+/// 將 LaTeX 轉為 MathML；無法轉換時輸出標示錯誤的原始碼
+pub fn render_math(source: &str, display: bool) -> String {
+    match math_to_mathml(source, display) {
+        Ok(mathml) => mathml,
+        Err(e) => format!(
+            "<code class=\"math-error\" title=\"{}\">{}</code>",
+            escape_html(&e),
+            escape_html(source)
+        ),
+    }
+}
+
+/// markdown 中的外部連結
+#[derive(Debug, Clone)]
+pub struct ExternalLink {
+    pub url: String,
+    pub text: String,
+}
+
+/// 依出現順序列出外部連結（不含圖片與指向 asset API 的連結），同一 URL 只列一次
+pub fn external_links(markdown: &str) -> Vec<ExternalLink> {
+    let mut links: Vec<ExternalLink> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut current: Option<ExternalLink> = None;
+    for event in Parser::new_ext(markdown, markdown_options()) {
+        match event {
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                current = Some(ExternalLink {
+                    url: dest_url.to_string(),
+                    text: String::new(),
+                });
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(link) = &mut current {
+                    link.text.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Link) => {
+                if let Some(link) = current.take()
+                    && (link.url.starts_with("http://") || link.url.starts_with("https://"))
+                    && !link.url.contains("/api/assets/")
+                    && seen.insert(link.url.clone())
+                {
+                    links.push(link);
+                }
+            }
+            _ => {}
+        }
+    }
+    links
+}
+
+/// 擷取所有標題作為目錄
+/// id 由標題文字產生（保留中日韓文字），重複時加上 `-1`、`-2`；以 `{#id}` 指定時沿用指定的 id
+/// 產生的 id 不會與任何指定的 id 相同；同一個 id 指定多次時，之後的標題同樣加上 `-1`、`-2`
+pub fn table_of_contents(markdown: &str) -> Vec<TocEntry> {
+    let mut headings = Vec::new();
+    // 目前標題的層級、指定的 id 與文字
+    let mut current: Option<(u8, Option<String>, String)> = None;
+    for event in Parser::new_ext(markdown, markdown_options()) {
+        match event {
+            Event::Start(Tag::Heading { level, id, .. }) => {
+                current = Some((level as u8, id.map(|id| id.to_string()), String::new()));
+            }
+            Event::Text(text) | Event::Code(text) | Event::InlineMath(text) => {
+                if let Some((_, _, heading)) = &mut current {
+                    heading.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => headings.extend(current.take()),
+            _ => {}
+        }
+    }
+
+    let reserved: HashSet<String> = headings.iter().filter_map(|(_, id, _)| id.clone()).collect();
+    let mut used = HashSet::new();
+    let mut entries = Vec::with_capacity(headings.len());
+    for (level, id, text) in headings {
+        let taken = |candidate: &str| used.contains(candidate) || reserved.contains(candidate);
+        let id = match id {
+            Some(id) if !used.contains(&id) => id,
+            Some(id) => unique_id(id, taken),
+            None => unique_id(slugify(&text), taken),
+        };
+        used.insert(id.clone());
+        entries.push(TocEntry {
+            level,
+            text: text.trim().to_string(),
+            id,
+        });
+    }
+    entries
+}
+
+/// 產生錨點用的 slug：保留文字與數字（包含中日韓文字）並轉為小寫，
+/// 空白、`-` 與 `_` 轉為單一 `-`，其他標點移除
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    let mut separator = false;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            if separator && !slug.is_empty() {
+                slug.push('-');
+            }
+            separator = false;
+            slug.extend(c.to_lowercase());
+        } else if c.is_whitespace() || c == '-' || c == '_' {
+            separator = true;
+        }
+    }
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug
+    }
+}
+
+fn unique_id(slug: String, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(&slug) {
+        return slug;
+    }
+    (1..)
+        .map(|n| format!("{}-{}", slug, n))
+        .find(|candidate| !taken(candidate))
+        .expect("unbounded range always yields a free id")
+}
+
+// 沒有 summary 與 `<!-- more -->` 時摘要取的字元數
+const EXCERPT_CHARS: usize = 200;
+
+/// 標示摘要結尾的 HTML 註解
+pub const MORE_MARKER: &str = "<!-- more -->";
+
+// 每分鐘閱讀的英文字數與中日文字數
+const WORDS_PER_MINUTE: f64 = 200.0;
+const CJK_CHARS_PER_MINUTE: f64 = 400.0;
+
+/// 由內容產生、列表中使用的摘要與閱讀資訊
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextMetadata {
+    pub excerpt: String,
+    /// 英文等以空白分詞的字數加上中日文字數
+    pub word_count: i32,
+    /// 預估閱讀時間（分鐘，無內容時為 0）
+    pub reading_minutes: i32,
+}
+
+/// 計算摘要、字數與閱讀時間
+///
+/// 摘要依序取 front matter 的 `summary`、`<!-- more -->` 之前的內容，
+/// 或純文字的前 200 個字元；標題與程式碼區塊不列入摘要，程式碼區塊也不計入字數
+pub fn text_metadata(markdown: &str, summary: Option<&str>) -> TextMetadata {
+    let (words, cjk_chars) = count_words(&plain_text(markdown, true));
+    let excerpt = match summary.map(str::trim).filter(|summary| !summary.is_empty()) {
+        Some(summary) => summary.to_string(),
+        None => match markdown.split_once(MORE_MARKER) {
+            Some((before, _)) => plain_text(before, false),
+            None => truncate(&plain_text(markdown, false), EXCERPT_CHARS),
+        },
+    };
+    let minutes = words as f64 / WORDS_PER_MINUTE + cjk_chars as f64 / CJK_CHARS_PER_MINUTE;
+    TextMetadata {
+        excerpt,
+        word_count: (words + cjk_chars) as i32,
+        reading_minutes: minutes.ceil() as i32,
+    }
+}
+
+/// 去除 markdown 語法後的文字，區塊之間以空白分隔
+fn plain_text(markdown: &str, include_headings: bool) -> String {
+    let mut text = String::new();
+    let mut in_code_block = false;
+    let mut in_heading = false;
+    for event in Parser::new_ext(markdown, markdown_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Start(Tag::Heading { .. }) => in_heading = true,
+            Event::End(TagEnd::Heading(_)) => {
+                in_heading = false;
+                text.push(' ');
+            }
+            Event::Text(content) | Event::Code(content) if !in_code_block && (include_headings || !in_heading) => {
+                text.push_str(&content);
+            }
+            Event::Text(_) | Event::Code(_) => {}
+            // 行內標記的結尾不是字的分界，否則 `*part*.` 會變成 `part .`
+            Event::End(
+                TagEnd::Emphasis
+                | TagEnd::Strong
+                | TagEnd::Strikethrough
+                | TagEnd::Superscript
+                | TagEnd::Subscript
+                | TagEnd::Link
+                | TagEnd::Image,
+            ) => {}
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 回傳（以空白分詞的字數, 中日文字數）
+/// 中文與日文假名不以空白分詞，每個字計為一個字；韓文以空白分詞
+fn count_words(text: &str) -> (usize, usize) {
+    let mut words = 0;
+    let mut cjk_chars = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk(c) {
+            cjk_chars += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                words += 1;
+            }
+            in_word = true;
+        } else if c != '\'' && c != '’' {
+            in_word = false;
+        }
+    }
+    (words, cjk_chars)
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'     // 平假名、片假名
+        | '\u{3400}'..='\u{4dbf}'   // 擴展 A
+        | '\u{4e00}'..='\u{9fff}'   // 基本區
+        | '\u{f900}'..='\u{faff}'   // 相容字
+        | '\u{20000}'..='\u{2ebef}' // 擴展 B–F
+    )
+}
+
+/// 超過 `max_chars` 時截斷並加上 `…`
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", text[..index].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+/// 所有解析 markdown 的地方使用相同的語法擴充，確保數學式內的文字不會被當成連結或標題
+pub fn markdown_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_MATH
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_HEADING_ATTRIBUTES
+}
+
+/// 跳脫 HTML 特殊字元，用於文字節點與屬性值
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_raw_html() {
+        let html = render_html("<script>alert(1)</script>\n\ntext <img src=x onerror=alert(1)> more\n");
+        assert!(!html.contains("<script>"), "{}", html);
+        assert!(!html.contains("<img"), "{}", html);
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"), "{}", html);
+        assert!(html.contains("&lt;img src=x onerror=alert(1)&gt;"), "{}", html);
+    }
+
+    #[test]
+    fn drops_html_comments() {
+        let html = render_html("intro\n\n<!-- more -->\n\nrest <!-- note --> here\n\n<!--\nmulti\nline\n-->\n");
+        assert!(!html.contains("more"), "{}", html);
+        assert!(!html.contains("note"), "{}", html);
+        assert!(!html.contains("multi"), "{}", html);
+        assert!(!html.contains("&lt;!--"), "{}", html);
+    }
+
+    fn ids(markdown: &str) -> Vec<String> {
+        table_of_contents(markdown).into_iter().map(|entry| entry.id).collect()
+    }
+
+    #[test]
+    fn explicit_ids_stay_unique() {
+        // 產生的 slug 避開之後才出現的指定 id
+        assert_eq!(ids("# Setup\n\n# Install {#setup}\n"), ["setup-1", "setup"]);
+        // 重複指定的 id
+        assert_eq!(ids("# A {#x}\n\n# B {#x}\n\n# X\n"), ["x", "x-1", "x-2"]);
+        let html = render_html("# Setup\n\n# Install {#setup}\n");
+        assert!(html.contains("<h1 id=\"setup-1\">Setup</h1>"), "{}", html);
+        assert!(html.contains("<h1 id=\"setup\">Install</h1>"), "{}", html);
+    }
+
+    #[test]
+    fn slugifies_cjk_headings() {
+        assert_eq!(slugify("安裝與設定"), "安裝與設定");
+        assert_eq!(slugify("Rust 入門：第一步"), "rust-入門第一步");
+        assert_eq!(slugify("  Hello,  World_again -- now! "), "hello-world-again-now");
+        assert_eq!(slugify("!!!"), "section");
+    }
+
+    #[test]
+    fn numbers_duplicate_headings() {
+        assert_eq!(ids("# 簡介\n\n## 簡介\n\n### 簡介\n"), ["簡介", "簡介-1", "簡介-2"]);
+        assert_eq!(ids("# Intro\n\n# Intro\n\n# Intro 1\n"), ["intro", "intro-1", "intro-1-1"]);
+    }
+
+    #[test]
+    fn counts_mixed_cjk_and_english() {
+        assert_eq!(count_words("Hello world，你好世界！"), (2, 4));
+        assert_eq!(count_words("don't stop"), (2, 0));
+        assert_eq!(count_words("ひらがな と カタカナ"), (0, 9));
+        // 韓文以空白分詞
+        assert_eq!(count_words("안녕 하세요"), (2, 0));
+        assert!(is_cjk('字') && is_cjk('あ') && is_cjk('𠀀') && !is_cjk('한') && !is_cjk('a'));
+    }
+
+    #[test]
+    fn estimates_reading_minutes() {
+        let english = "word ".repeat(200);
+        let chinese = "字".repeat(400);
+        let metadata = text_metadata(&format!("{}\n\n{}\n", english, chinese), None);
+        assert_eq!(metadata.word_count, 600);
+        assert_eq!(metadata.reading_minutes, 2);
+
+        // 程式碼區塊不計入字數
+        let metadata = text_metadata("one two\n\n```\nlet x = 1;\n```\n", None);
+        assert_eq!(metadata.word_count, 2);
+        assert_eq!(metadata.reading_minutes, 1);
+
+        assert_eq!(text_metadata("", None).reading_minutes, 0);
+    }
+
+    #[test]
+    fn truncates_on_char_boundary() {
+        assert_eq!(truncate("中文摘要測試", 4), "中文摘要…");
+        assert_eq!(truncate("ab 🎉🎉", 4), "ab 🎉…");
+        assert_eq!(truncate("short", 10), "short");
+        let excerpt = text_metadata(&"漢".repeat(300), None).excerpt;
+        assert_eq!(excerpt.chars().count(), EXCERPT_CHARS + 1);
+        assert!(excerpt.ends_with('…'));
+    }
+
+    #[test]
+    fn picks_excerpt_source() {
+        let markdown = "# Title\n\nFirst *part*.\n\n<!-- more -->\n\nRest of the post.\n";
+        assert_eq!(text_metadata(markdown, None).excerpt, "First part.");
+        assert_eq!(text_metadata(markdown, Some("  Custom summary ")).excerpt, "Custom summary");
+        assert_eq!(text_metadata(markdown, Some("  ")).excerpt, "First part.");
+        assert_eq!(text_metadata("# 標題\n\n內文。\n", None).excerpt, "內文。");
+    }
+
+    #[test]
+    fn keeps_generated_html() {
+        let html = render_html("```mermaid\ngraph TD; A-->B\n```\n\n$x^2$\n");
+        assert!(html.contains("<pre class=\"mermaid\">"), "{}", html);
+        assert!(html.contains("<math"), "{}", html);
+    }
+}
@@ -0,0 +1,32 @@
+use std::env;
+use std::time::Instant;
+use tracing::level_filters::LevelFilter;
+use tracing::{debug, debug_span, Instrument};
+use tracing_subscriber::EnvFilter;
+
+/// 初始化 tracing，日誌一律輸出到 stderr
+///
+/// - `RUST_LOG` 有設定時優先使用，否則使用 `default_level`
+/// - `LOG_FORMAT`：`json`（一行一筆 JSON）、`pretty`（多行易讀格式），其他值為單行文字
+pub fn init(default_level: LevelFilter) {
+    let filter = EnvFilter::builder()
+        .with_default_directive(default_level.into())
+        .from_env_lossy();
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+
+    match env::var("LOG_FORMAT").unwrap_or_default().to_ascii_lowercase().as_str() {
+        "json" => builder.json().with_current_span(true).with_span_list(true).init(),
+        "pretty" => builder.pretty().init(),
+        _ => builder.init(),
+    }
+}
+
+/// 在 `db.query` span 中執行資料庫查詢並記錄耗時，API 與 CLI 共用
+pub async fn traced_query<F: Future>(query: &str, future: F) -> F::Output {
+    let started = Instant::now();
+    let output = future.instrument(debug_span!("db.query", query)).await;
+    debug!(query, elapsed_ms = started.elapsed().as_secs_f64() * 1000.0, "query finished");
+    output
+}
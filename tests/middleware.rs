@@ -0,0 +1,53 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::{test, web};
+use journal_core::api::{self, config::{ApiConfig, Quota}, rate_limit::RateLimiter};
+
+// 明確建立設定，不讀取環境變數；JSON 端點每個 IP 只允許一個請求
+fn config() -> ApiConfig {
+    ApiConfig {
+        post_max_age: 60,
+        response_cache_capacity: 0,
+        response_cache_ttl: 0,
+        public_base_url: Some("http://localhost:8080".to_string()),
+        shutdown_timeout: 1,
+        shutdown_drain_delay: 0,
+        json_rate_limit: Quota { per_minute: 1, burst: 1 },
+        asset_rate_limit: Quota { per_minute: 0, burst: 0 },
+        trusted_proxies: Vec::new(),
+        proxy_header: "X-Forwarded-For".to_string(),
+        rate_limit_allowlist: Vec::new(),
+        serve_static: false,
+        admin_token: None,
+        highlight_theme: "InspiredGitHub".to_string(),
+    }
+}
+
+#[actix_web::test]
+async fn rate_limited_responses_carry_cors_headers() {
+    let config = config();
+    let limiter = RateLimiter::new(&config);
+    let app = test::init_service(
+        api::app_with_middleware()
+            .app_data(web::Data::new(limiter))
+            .app_data(web::Data::new(config)),
+    )
+    .await;
+
+    let request = || {
+        test::TestRequest::get()
+            .uri("/api/highlight/languages")
+            .peer_addr("203.0.113.7:40000".parse().unwrap())
+            .insert_header((header::ORIGIN, "http://localhost:3000"))
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::call_service(&app, request()).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(
+        resp.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+        "http://localhost:3000"
+    );
+}
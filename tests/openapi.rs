@@ -0,0 +1,62 @@
+use actix_web::body::to_bytes;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::{test, Error, HttpResponse};
+use journal_core::api;
+use std::future::{ready, Ready};
+
+// 取代所有 handler 的 stub：不執行 handler，回應請求對應到的路由樣式，沒有對應的路由時回應 404
+fn route_stub(req: ServiceRequest) -> Ready<Result<ServiceResponse, Error>> {
+    let response = match req.match_pattern() {
+        Some(pattern) => HttpResponse::Ok().body(pattern),
+        None => HttpResponse::NotFound().finish(),
+    };
+    ready(Ok(req.into_response(response)))
+}
+
+#[actix_web::test]
+async fn served_spec_matches_registered_routes() {
+    let app = test::init_service(api::app()).await;
+    let routes = test::init_service(api::app().wrap_fn(|req, _| route_stub(req))).await;
+
+    let req = test::TestRequest::get().uri("/api/openapi.json").to_request();
+    let spec: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+    let paths = spec["paths"].as_object().expect("spec has no paths");
+    for expected in [
+        "/api/posts",
+        "/api/posts/{uuid}",
+        "/api/posts/{uuid}/references",
+        "/api/assets/{uuid}",
+        "/api/posts/{uuid}/assets",
+        "/api/highlight/languages",
+        "/api/highlight/themes",
+        "/api/highlight.css",
+    ] {
+        assert!(paths.contains_key(expected), "{} is missing from the spec", expected);
+    }
+
+    // 文件中的每個路徑都必須對應到相同樣式的路由
+    for (path, item) in paths {
+        for method in item.as_object().unwrap().keys() {
+            assert_eq!(method, "get", "unexpected method {} for {}", method, path);
+        }
+        let req = test::TestRequest::get().uri(path).to_request();
+        let resp = test::call_service(&routes, req).await;
+        assert_eq!(resp.status(), StatusCode::OK, "{} is documented but not routed", path);
+        let pattern = to_bytes(resp.into_body()).await.unwrap();
+        assert_eq!(pattern, path.as_bytes(), "{} is routed to a different pattern", path);
+    }
+
+    // 不在文件中的路由必須是刻意排除的
+    for path in api::UNDOCUMENTED_ROUTES {
+        assert!(!paths.contains_key(*path), "{} should not be documented", path);
+        let req = test::TestRequest::get().uri(path).to_request();
+        let resp = test::call_service(&routes, req).await;
+        assert_eq!(resp.status(), StatusCode::OK, "{} is listed as undocumented but not routed", path);
+    }
+
+    let req = test::TestRequest::get().uri("/api/docs").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}